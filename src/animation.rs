@@ -0,0 +1,140 @@
+//! Animated GIFs of a running 2D field, written frame by frame as the simulation goes.
+
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::{Path, PathBuf};
+
+use gif::{Encoder, Frame, Repeat};
+
+use crate::colormap::{field_pixels, ColorRange, ColorScale, Colormap};
+use crate::field::Field;
+use crate::label::from_label;
+
+/// quantizer speed from 1 (best) to 30 (fastest), see `gif::Frame::from_rgb_speed`
+const QUANTIZE_SPEED: i32 = 10;
+
+/// What goes into a GIF and how fast it plays.
+#[derive(Copy, Clone, Debug)]
+pub struct GifOptions {
+    /// steps between frames
+    pub every: u32,
+    /// time each frame is shown, in hundredths of a second
+    pub delay: u16,
+    pub colormap: Colormap,
+    /// displacement at full color, 0 to fit every frame to the field
+    pub range: f32,
+    /// number of frames before the GIF is finished, 0 for no limit
+    pub frames: u32,
+    /// pixels per cell
+    pub scale: u16,
+}
+
+impl Default for GifOptions {
+    fn default() -> Self {
+        Self { every: 4, delay: 4, colormap: Colormap::Classic, range: 255., frames: 100, scale: 2 }
+    }
+}
+
+impl GifOptions {
+    /// Set the option named `key`, as in `--gif-<key>=value` on the command line. Returns
+    /// `Ok(false)` if there's no such option.
+    pub fn set(&mut self, key: &str, value: &str) -> Result<bool, String> {
+        let invalid = || format!("invalid value `{value}` for --gif-{key}");
+        match key {
+            "every" => self.every = value.parse::<u32>().map_err(|_| invalid())?.max(1),
+            "delay" => self.delay = value.parse().map_err(|_| invalid())?,
+            "frames" => self.frames = value.parse().map_err(|_| invalid())?,
+            "range" => self.range = value.parse::<f32>().map_err(|_| invalid())?.max(0.),
+            "scale" => self.scale = value.parse::<u16>().map_err(|_| invalid())?.max(1),
+            "palette" => self.colormap = from_label(value).map_err(|err| format!("{err} for --gif-{key}"))?,
+            _ => return Ok(false),
+        }
+        Ok(true)
+    }
+}
+
+/// A GIF being written, which takes a frame whenever the field reaches a multiple of
+/// `options.every` steps.
+pub struct GifWriter {
+    encoder: Encoder<BufWriter<File>>,
+    pub path: PathBuf,
+    pub options: GifOptions,
+    /// size of the field in cells
+    width: usize,
+    height: usize,
+    pub written: u32,
+}
+
+impl GifWriter {
+    /// Start a GIF at `path` for frames of `field`, which has to be 2D.
+    pub fn create(path: &Path, field: &dyn Field, options: GifOptions) -> Result<GifWriter, String> {
+        let &[width, height] = field.u().shape() else {
+            return Err("GIFs can only be recorded from 2D fields".to_string());
+        };
+        let error = |err: &dyn std::fmt::Display| format!("{}: {err}", path.display());
+        let scale = options.scale as usize;
+        let (gif_width, gif_height) = (width * scale, height * scale);
+        if gif_width > u16::MAX as usize || gif_height > u16::MAX as usize {
+            return Err(format!("a {gif_width}x{gif_height} GIF is too large, lower --gif-scale"));
+        }
+        let file = File::create(path).map_err(|err| error(&err))?;
+        let mut encoder =
+            Encoder::new(BufWriter::new(file), gif_width as u16, gif_height as u16, &[]).map_err(|err| error(&err))?;
+        encoder.set_repeat(Repeat::Infinite).map_err(|err| error(&err))?;
+        Ok(GifWriter { encoder, path: path.to_path_buf(), options, width, height, written: 0 })
+    }
+
+    /// Add a frame if `field` is at a step that gets one. Returns whether it did.
+    pub fn capture(&mut self, field: &dyn Field) -> Result<bool, String> {
+        if !field.step().is_multiple_of(self.options.every) || self.done() {
+            return Ok(false);
+        }
+        self.add_frame(field)?;
+        Ok(true)
+    }
+
+    /// Add the current state of `field` as the next frame.
+    pub fn add_frame(&mut self, field: &dyn Field) -> Result<(), String> {
+        let range = if self.options.range > 0. {
+            ColorRange::default().scaled(self.options.range / ColorRange::default().u)
+        } else {
+            ColorRange::fit(field)
+        };
+        let colors = ColorScale { colormap: self.options.colormap, range };
+        let Some((width, height, pixels)) = field_pixels(field, colors) else {
+            return Err("GIFs can only be recorded from 2D fields".to_string());
+        };
+        if (width, height) != (self.width, self.height) {
+            return Err(format!("the field changed size to {width}x{height} while recording"));
+        }
+        let scale = self.options.scale as usize;
+        let mut scaled = Vec::with_capacity(pixels.len() * scale * scale);
+        for row in pixels.chunks(width * 3) {
+            let mut scaled_row = Vec::with_capacity(row.len() * scale);
+            for pixel in row.chunks(3) {
+                for _ in 0..scale {
+                    scaled_row.extend_from_slice(pixel);
+                }
+            }
+            for _ in 0..scale {
+                scaled.extend_from_slice(&scaled_row);
+            }
+        }
+        let mut frame =
+            Frame::from_rgb_speed((width * scale) as u16, (height * scale) as u16, &scaled, QUANTIZE_SPEED);
+        frame.delay = self.options.delay;
+        self.encoder.write_frame(&frame).map_err(|err| err.to_string())?;
+        self.written += 1;
+        Ok(())
+    }
+
+    /// Whether the GIF has all the frames it was asked for.
+    pub fn done(&self) -> bool {
+        self.options.frames > 0 && self.written >= self.options.frames
+    }
+
+    /// Write the end of the GIF and close the file.
+    pub fn finish(self) -> Result<(), String> {
+        self.encoder.into_inner().map(|_| ()).map_err(|err| err.to_string())
+    }
+}
@@ -0,0 +1,373 @@
+//! Runs a field without opening a window and writes its state and energy to disk.
+//!
+//! ```text
+//! cargo run --release --bin batch -- --dims=2 --init=double-slit --steps=2000 \
+//!     --snapshot-every=100 --out=runs/double-slit
+//! ```
+
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::process::exit;
+
+use ndarray::ArrayViewD;
+use pixel_wave_simulator::animation::{GifOptions, GifWriter};
+use pixel_wave_simulator::field::{Field, Field1D, Field1DInit, Field2D, Field2DInit, FieldConfig};
+use pixel_wave_simulator::label::{from_label, Labeled};
+use pixel_wave_simulator::probe::Probes;
+use pixel_wave_simulator::scenario::Scenario;
+use pixel_wave_simulator::snapshot::Snapshot;
+use pixel_wave_simulator::spectrum::Spectrum;
+
+const USAGE: &str =
+    "usage: batch [--dims=1|2] [--init=NAME] [--steps=N] [--snapshot-every=N] [--out=DIR]
+             [--vectorized] [--boundary=NAME] [--medium=NAME] [--integrator=NAME]
+             [--damping=X] [--dt=X] [--width=N] [--height=N] [--scenario=FILE]
+             [--resume=FILE] [--gif=FILE] [--gif-every=N] [--gif-delay=CS]
+             [--gif-frames=N] [--gif-range=X] [--gif-scale=N] [--gif-palette=NAME]
+             [--probe=X,Y]...
+
+Writes energy.csv with one row of diagnostics per step, u_<step>.csv every
+--snapshot-every steps, and final_u.csv / final_v.csv plus the full state in
+final.npz at the end. Names are the
+labels shown in the app, with spaces written as dashes, e.g. --init=double-slit.
+--width and --height default to the size the init was designed at.
+--scenario sets the field up from a TOML or JSON scenario file instead of
+--dims, --init and the size, with the other options overriding its settings.
+--resume carries on from a saved .npz snapshot the same way.
+--gif also writes an animated GIF of a 2D field with a frame every --gif-every
+steps (default 4), each shown for --gif-delay hundredths of a second (4), up to
+--gif-frames frames (100, 0 for the whole run), --gif-scale pixels per cell (2)
+and colored with --gif-palette (classic, diverging, viridis, grayscale or
+phase) at full color for displacements of --gif-range (255, 0 to fit every
+frame).
+--probe records u and v at cell X,Y (X alone in 1D) every step into
+probes.csv, with one row of probe,x,y,step,u,v per sample. Repeat it for
+more probes. The power spectrum of each probe's u goes into spectrum.csv as
+rows of probe,frequency,power, with frequencies in cycles per step, and its
+strongest peaks are printed next to the field's fundamental frequency.";
+
+struct Args {
+    dims: usize,
+    init: String,
+    steps: u32,
+    /// 0 to only write the final state
+    snapshot_every: u32,
+    out: PathBuf,
+    vectorized: bool,
+    config: FieldConfig,
+    /// `None` to use the init's own size
+    width: Option<usize>,
+    height: Option<usize>,
+    scenario: Option<Scenario>,
+    resume: Option<Snapshot>,
+    /// where to write a GIF of the run, if anywhere
+    gif: Option<PathBuf>,
+    gif_options: GifOptions,
+    /// cells to record u and v at
+    probes: Vec<(usize, usize)>,
+}
+
+fn main() {
+    let args = match parse_args(std::env::args().skip(1)) {
+        Ok(args) => args,
+        Err(message) => {
+            eprintln!("{message}\n\n{USAGE}");
+            exit(2);
+        }
+    };
+    let mut field = match build_field(&args)
+        .and_then(|field| check_probes(&args, field.as_ref()).map(|_| field))
+    {
+        Ok(field) => field,
+        Err(message) => {
+            eprintln!("{message}");
+            exit(2);
+        }
+    };
+    let gif = match args
+        .gif
+        .as_ref()
+        .map(|path| start_gif(path, field.as_ref(), args.gif_options))
+        .transpose()
+    {
+        Ok(gif) => gif,
+        Err(message) => {
+            eprintln!("{message}");
+            exit(2);
+        }
+    };
+    match run(field.as_mut(), gif, &args) {
+        Ok(true) => {}
+        Ok(false) => exit(1),
+        Err(err) => {
+            eprintln!("failed to write to {}: {err}", args.out.display());
+            exit(1);
+        }
+    }
+}
+
+fn parse_args(args: impl Iterator<Item = String>) -> Result<Args, String> {
+    let args: Vec<String> = args.collect();
+    // load the scenario or snapshot up front so the options can override their settings,
+    // wherever they appear
+    let scenario = args
+        .iter()
+        .find_map(|arg| arg.strip_prefix("--scenario="))
+        .map(|path| Scenario::load(path.as_ref()))
+        .transpose()?;
+    let resume = args
+        .iter()
+        .find_map(|arg| arg.strip_prefix("--resume="))
+        .map(|path| Snapshot::load(path.as_ref()))
+        .transpose()?;
+    let config = match (&resume, &scenario) {
+        (Some(snapshot), _) => snapshot.config,
+        (None, Some(scenario)) => scenario.config.into(),
+        (None, None) => FieldConfig::default(),
+    };
+    let mut parsed = Args {
+        dims: 2,
+        init: String::new(),
+        steps: 1000,
+        snapshot_every: 0,
+        out: PathBuf::from("out"),
+        vectorized: resume.as_ref().is_some_and(|snapshot| snapshot.vectorized),
+        config,
+        width: None,
+        height: None,
+        scenario,
+        resume,
+        gif: None,
+        gif_options: GifOptions::default(),
+        probes: vec![],
+    };
+    for arg in args {
+        if arg == "--help" || arg == "-h" {
+            println!("{USAGE}");
+            exit(0);
+        }
+        if arg == "--vectorized" {
+            parsed.vectorized = true;
+            continue;
+        }
+        let Some((key, value)) = arg.split_once('=') else {
+            return Err(format!("unexpected argument `{arg}`"));
+        };
+        let config = &mut parsed.config;
+        match key {
+            "--dims" => parsed.dims = parse_number(key, value)?,
+            "--init" => parsed.init = value.to_string(),
+            "--steps" => parsed.steps = parse_number(key, value)?,
+            "--snapshot-every" => parsed.snapshot_every = parse_number(key, value)?,
+            "--out" => parsed.out = PathBuf::from(value),
+            "--boundary" => config.boundary = parse_label(key, value)?,
+            "--medium" => config.medium = parse_label(key, value)?,
+            "--integrator" => config.integrator = parse_label(key, value)?,
+            "--damping" => config.damping = parse_number(key, value)?,
+            "--dt" => config.dt = parse_number(key, value)?,
+            "--width" => parsed.width = Some(parse_number(key, value)?),
+            "--height" => parsed.height = Some(parse_number(key, value)?),
+            "--gif" => parsed.gif = Some(PathBuf::from(value)),
+            "--probe" => {
+                let (x, y) = value.split_once(',').unwrap_or((value, "0"));
+                let cell = (parse_number(key, x)?, parse_number(key, y)?);
+                // placing a probe twice would take it away again
+                if !parsed.probes.contains(&cell) {
+                    parsed.probes.push(cell);
+                }
+            }
+            "--scenario" | "--resume" => {}
+            _ => match key.strip_prefix("--gif-") {
+                Some(option) if parsed.gif_options.set(option, value)? => {}
+                _ => return Err(format!("unknown option `{key}`")),
+            },
+        }
+    }
+    if parsed.dims != 1 && parsed.dims != 2 {
+        return Err(format!("--dims must be 1 or 2, got {}", parsed.dims));
+    }
+    Ok(parsed)
+}
+
+fn parse_number<T: std::str::FromStr>(key: &str, value: &str) -> Result<T, String> {
+    value
+        .parse()
+        .map_err(|_| format!("invalid value `{value}` for {key}"))
+}
+
+fn parse_label<T: Labeled>(key: &str, value: &str) -> Result<T, String> {
+    from_label(value).map_err(|err| format!("{err} for {key}"))
+}
+
+fn build_field(args: &Args) -> Result<Box<dyn Field>, String> {
+    if let Some(snapshot) = &args.resume {
+        return snapshot.restore(args.config, args.vectorized);
+    }
+    if let Some(scenario) = &args.scenario {
+        return scenario.build(args.config, args.vectorized);
+    }
+    Ok(if args.dims == 1 {
+        let init = if args.init.is_empty() {
+            Field1DInit::AtEnd
+        } else {
+            parse_label("--init", &args.init)?
+        };
+        Box::new(Field1D::new(
+            init,
+            args.width.unwrap_or(init.default_width()),
+            args.config,
+        ))
+    } else {
+        let init = if args.init.is_empty() {
+            Field2DInit::Standing
+        } else {
+            parse_label("--init", &args.init)?
+        };
+        let (width, height) = init.default_size();
+        let size = (args.width.unwrap_or(width), args.height.unwrap_or(height));
+        Box::new(Field2D::new(args.vectorized, init, size, args.config))
+    })
+}
+
+/// Fail if a probe sits outside `field`, where it would never record anything.
+fn check_probes(args: &Args, field: &dyn Field) -> Result<(), String> {
+    let shape = field.u().shape().to_vec();
+    // 1D fields ignore y
+    let outside = |&&(x, y): &&(usize, usize)| {
+        x >= shape[0] || shape.get(1).is_some_and(|&height| y >= height)
+    };
+    match args.probes.iter().find(outside) {
+        Some((x, y)) => Err(format!("--probe={x},{y} is outside the {shape:?} grid")),
+        None => Ok(()),
+    }
+}
+
+/// Create the GIF at `path` with `field` as its first frame.
+fn start_gif(path: &Path, field: &dyn Field, options: GifOptions) -> Result<GifWriter, String> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).map_err(|err| format!("{}: {err}", dir.display()))?;
+    }
+    let mut gif = GifWriter::create(path, field, options)?;
+    gif.add_frame(field)?;
+    Ok(gif)
+}
+
+/// Step the field and write everything out. Returns `Ok(false)` if the field diverged, in which
+/// case the run stops early but the state up to that point is still written.
+fn run(field: &mut dyn Field, mut gif: Option<GifWriter>, args: &Args) -> io::Result<bool> {
+    fs::create_dir_all(&args.out)?;
+    let mut probes = Probes::with_history(args.steps as usize + 1);
+    for &cell in &args.probes {
+        probes.toggle(cell).map_err(io::Error::other)?;
+    }
+    probes.record(field);
+    let mut energy = BufWriter::new(File::create(args.out.join("energy.csv"))?);
+    writeln!(energy, "step,kinetic,potential,total,drift,l2,max")?;
+    write_energy(&mut energy, field)?;
+    if args.snapshot_every > 0 {
+        write_snapshot(field, &args.out)?;
+    }
+
+    let mut stable = true;
+    for _ in 0..args.steps {
+        field.update();
+        probes.record(field);
+        write_energy(&mut energy, field)?;
+        if args.snapshot_every > 0 && field.step().is_multiple_of(args.snapshot_every) {
+            write_snapshot(field, &args.out)?;
+        }
+        if let Some(gif) = &mut gif {
+            gif.capture(field).map_err(io::Error::other)?;
+        }
+        if field.diverged() {
+            eprintln!(
+                "field diverged at step {}, CFL {:.3} (limit {:.3})",
+                field.step(),
+                field.cfl(),
+                field.cfl_limit()
+            );
+            stable = false;
+            break;
+        }
+    }
+    energy.flush()?;
+    if let Some(gif) = gif {
+        gif.finish().map_err(io::Error::other)?;
+    }
+
+    if !probes.is_empty() {
+        probes
+            .write_csv(&args.out.join("probes.csv"))
+            .map_err(io::Error::other)?;
+        write_spectra(&args.out.join("spectrum.csv"), &probes, field)?;
+    }
+    write_csv(&args.out.join("final_u.csv"), field.u())?;
+    write_csv(&args.out.join("final_v.csv"), field.v())?;
+    Snapshot::capture(field, args.config, args.vectorized)
+        .save(&args.out.join("final.npz"))
+        .map_err(io::Error::other)?;
+    Ok(stable)
+}
+
+fn write_energy(out: &mut impl Write, field: &dyn Field) -> io::Result<()> {
+    let d = field.diagnostics();
+    writeln!(
+        out,
+        "{},{},{},{},{},{},{}",
+        field.step(),
+        d.kinetic,
+        d.potential,
+        d.total(),
+        d.drift,
+        d.l2,
+        d.max
+    )
+}
+
+/// Write the spectrum of every probe's u and print its strongest peaks.
+fn write_spectra(path: &Path, probes: &Probes, field: &dyn Field) -> io::Result<()> {
+    let mut out = BufWriter::new(File::create(path)?);
+    writeln!(out, "probe,frequency,power")?;
+    if let Some(frequency) = field.fundamental_frequency() {
+        println!("fundamental: {frequency:.4e}/step");
+    }
+    for (i, probe) in probes.iter().enumerate() {
+        let Some(spectrum) =
+            Spectrum::of(&probe.samples().map(|sample| sample.u).collect::<Vec<f32>>())
+        else {
+            continue;
+        };
+        for (bin, power) in spectrum.power.iter().enumerate() {
+            writeln!(out, "{i},{},{power}", spectrum.frequency(bin))?;
+        }
+        let peaks: Vec<String> = spectrum
+            .peaks(3)
+            .iter()
+            .map(|peak| format!("{:.4e}", peak.frequency))
+            .collect();
+        println!(
+            "probe {i} at {:?}: peaks at {}/step",
+            probe.cell,
+            peaks.join(", ")
+        );
+    }
+    out.flush()
+}
+
+fn write_snapshot(field: &dyn Field, dir: &Path) -> io::Result<()> {
+    write_csv(&dir.join(format!("u_{:06}.csv", field.step())), field.u())
+}
+
+/// Write a 1D array as a single row, or a 2D array with one row per y.
+fn write_csv(path: &Path, values: ArrayViewD<'_, f32>) -> io::Result<()> {
+    let mut out = BufWriter::new(File::create(path)?);
+    let shape = values.shape();
+    let (width, height) = (shape[0], shape.get(1).copied().unwrap_or(1));
+    let values = values.into_shape_with_order((width, height)).unwrap();
+    for y in 0..height {
+        let row: Vec<String> = (0..width).map(|x| values[(x, y)].to_string()).collect();
+        writeln!(out, "{}", row.join(","))?;
+    }
+    out.flush()
+}
@@ -1,5 +1,6 @@
 use ndarray::prelude::*;
 
+#[allow(clippy::reversed_empty_ranges)]
 fn main() {
     let a0: Array0<f32> = Array0::default(());
     println!("{a0:?}");
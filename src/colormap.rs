@@ -0,0 +1,204 @@
+//! Colors of the heatmap, shared by the renderers and the exporters.
+
+use std::f32::consts::PI;
+
+use ndarray::Ix2;
+
+use crate::field::Field;
+
+/// color of obstacle cells
+pub const OBSTACLE: [u8; 3] = [130, 130, 130];
+
+/// viridis sampled at nine evenly spaced points
+const VIRIDIS: [[u8; 3]; 9] = [
+    [68, 1, 84],
+    [71, 44, 122],
+    [59, 81, 139],
+    [44, 113, 142],
+    [33, 144, 141],
+    [39, 173, 129],
+    [92, 200, 99],
+    [170, 220, 50],
+    [253, 231, 37],
+];
+/// blue, white and red ends and middle of the diverging map
+const DIVERGING: [[u8; 3]; 3] = [[59, 76, 192], [242, 242, 242], [180, 4, 38]];
+
+/// How a cell's displacement maps to a color.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum Colormap {
+    /// positive displacement red, negative green, slow media tinted blue
+    Classic,
+    /// negative blue through white at rest to positive red
+    Diverging,
+    /// perceptually uniform dark blue through green to yellow, readable with color blindness
+    Viridis,
+    /// black through mid gray at rest to white
+    Grayscale,
+    /// hue from the phase of the oscillation in `(u, v)`, brightness from its amplitude
+    Phase,
+}
+
+impl Colormap {
+    pub fn cycle(self) -> Self {
+        match self {
+            Self::Classic => Self::Diverging,
+            Self::Diverging => Self::Viridis,
+            Self::Viridis => Self::Grayscale,
+            Self::Grayscale => Self::Phase,
+            Self::Phase => Self::Classic,
+        }
+    }
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Classic => "classic",
+            Self::Diverging => "diverging",
+            Self::Viridis => "viridis",
+            Self::Grayscale => "grayscale",
+            Self::Phase => "phase",
+        }
+    }
+
+    /// Color of a cell with displacement `u` and velocity `v`, at full color at the edges of
+    /// `range`. `tint` marks slow media, which only the classic map shows.
+    pub fn color(self, u: f32, v: f32, range: ColorRange, tint: u8) -> [u8; 3] {
+        let x = (u / range.u).clamp(-1., 1.);
+        match self {
+            Self::Classic => [(x * 255.) as u8, (-x * 255.) as u8, tint],
+            Self::Diverging => interpolate(&DIVERGING, (x + 1.) / 2.),
+            Self::Viridis => interpolate(&VIRIDIS, (x + 1.) / 2.),
+            Self::Grayscale => {
+                let gray = ((x + 1.) / 2. * 255.) as u8;
+                [gray, gray, gray]
+            }
+            Self::Phase => {
+                let y = (v / range.v).clamp(-1., 1.);
+                hsv(y.atan2(x), x.hypot(y).min(1.))
+            }
+        }
+    }
+
+    /// Color at `t` from -1 to 1 along the legend. For the phase map that's the phase from -π
+    /// to π at full amplitude, for the others the displacement relative to the range.
+    pub fn legend(self, t: f32) -> [u8; 3] {
+        match self {
+            Self::Phase => hsv(t * PI, 1.),
+            _ => self.color(t, 0., ColorRange { u: 1., v: 1. }, 0),
+        }
+    }
+}
+
+/// Displacement and velocity shown at full color; anything beyond saturates.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct ColorRange {
+    pub u: f32,
+    /// only used by the phase map
+    pub v: f32,
+}
+
+impl Default for ColorRange {
+    /// the fixed range of the original red and green map
+    fn default() -> Self {
+        Self { u: 255., v: 64. }
+    }
+}
+
+impl ColorRange {
+    /// smallest range, so a field at rest doesn't blow noise up to full color
+    const MIN: f32 = 1e-3;
+
+    /// The range spanning the largest displacement in `field`. The velocity range is scaled from
+    /// it by the ratio of the velocity's and the displacement's norms, so that oscillations
+    /// go all the way around the phase map rather than being dominated by the fastest cell.
+    pub fn fit(field: &dyn Field) -> Self {
+        let max_abs = field.u().iter().fold(0f32, |max, x| max.max(x.abs()));
+        let norm = |values: ndarray::ArrayViewD<'_, f32>| values.iter().map(|x| x * x).sum::<f32>().sqrt();
+        let (u_norm, v_norm) = (norm(field.u()), norm(field.v()));
+        let u = max_abs.max(Self::MIN);
+        let v = if u_norm > 0. { u * v_norm / u_norm } else { Self::default().v };
+        Self { u, v: v.max(Self::MIN) }
+    }
+
+    /// The range scaled by `factor`.
+    pub fn scaled(self, factor: f32) -> Self {
+        Self {
+            u: (self.u * factor).max(Self::MIN),
+            v: (self.v * factor).max(Self::MIN),
+        }
+    }
+}
+
+/// A colormap together with the range it spans.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct ColorScale {
+    pub colormap: Colormap,
+    pub range: ColorRange,
+}
+
+impl Default for ColorScale {
+    fn default() -> Self {
+        Self {
+            colormap: Colormap::Classic,
+            range: ColorRange::default(),
+        }
+    }
+}
+
+impl ColorScale {
+    /// Color of a cell, see `Colormap::color`.
+    pub fn color(self, u: f32, v: f32, tint: u8) -> [u8; 3] {
+        self.colormap.color(u, v, self.range, tint)
+    }
+}
+
+/// Linear interpolation between evenly spaced `stops` at `t` from 0 to 1.
+fn interpolate(stops: &[[u8; 3]], t: f32) -> [u8; 3] {
+    let pos = t.clamp(0., 1.) * (stops.len() - 1) as f32;
+    let i = (pos as usize).min(stops.len() - 2);
+    let frac = pos - i as f32;
+    let mix = |a: u8, b: u8| (a as f32 + (b as f32 - a as f32) * frac).round() as u8;
+    [0, 1, 2].map(|c| mix(stops[i][c], stops[i + 1][c]))
+}
+
+/// Fully saturated color of hue `angle` in radians, red at 0, and brightness `value`.
+fn hsv(angle: f32, value: f32) -> [u8; 3] {
+    let h = angle.rem_euclid(2. * PI) / (PI / 3.);
+    let x = 1. - (h % 2. - 1.).abs();
+    let [r, g, b] = match h as u32 {
+        0 => [1., x, 0.],
+        1 => [x, 1., 0.],
+        2 => [0., 1., x],
+        3 => [0., x, 1.],
+        4 => [x, 0., 1.],
+        _ => [1., 0., x],
+    };
+    [r, g, b].map(|c: f32| (c * value * 255.) as u8)
+}
+
+/// Blue tint of a cell marking how much slower waves travel there than in the fastest cell.
+pub fn medium_tint(c2: f32, c2_max: f32) -> u8 {
+    ((1. - c2 / c2_max) * 128.) as u8
+}
+
+/// RGB bytes of a 2D field's heatmap, row by row from y = 0, along with its width and height.
+/// `None` for 1D fields.
+pub fn field_pixels(field: &dyn Field, colors: ColorScale) -> Option<(usize, usize, Vec<u8>)> {
+    let u = field.u().into_dimensionality::<Ix2>().ok()?;
+    let v = field.v().into_dimensionality::<Ix2>().ok()?;
+    let c2 = field.c2().into_dimensionality::<Ix2>().ok()?;
+    let mask = field.mask().map(|mask| mask.into_dimensionality::<Ix2>().unwrap());
+    let (width, height) = u.dim();
+    let c2_max = c2.iter().cloned().fold(0., f32::max);
+    let mut pixels = Vec::with_capacity(width * height * 3);
+    for y in 0..height {
+        for x in 0..width {
+            if mask.as_ref().is_some_and(|mask| mask[(x, y)]) {
+                pixels.extend(OBSTACLE);
+            } else {
+                let tint = medium_tint(c2[(x, y)], c2_max);
+                pixels.extend(colors.color(u[(x, y)], v[(x, y)], tint));
+            }
+        }
+    }
+    Some((width, height, pixels))
+}
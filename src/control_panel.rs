@@ -5,7 +5,7 @@ use macroquad::{
     window::{screen_height, screen_width},
 };
 
-use crate::{game::GameState, observer::Observer};
+use crate::{game::GameState, observer::Observer, render::short_number};
 
 const BAR_HEIGHT: f32 = 28.0;
 const FONT_SIZE: f32 = 16.0;
@@ -17,8 +17,41 @@ const SHORTCUTS: &[(&str, &str)] = &[
     ("R", "Reset"),
     ("T", "Reset (vec)"),
     ("N", "Next init"),
+    ("PgUp/PgDn", "Grid size"),
+    ("Shift PgUp/PgDn", "Width"),
+    ("Home", "Preset size"),
+    ("O", "Pick scenario"),
+    ("F5", "Save snapshot"),
+    ("F9", "Load latest snapshot"),
+    ("P", "Record PNGs"),
+    ("G", "Record GIF"),
+    ("B", "Boundary"),
+    ("M", "Medium"),
+    ("D/Shift+D", "Damping"),
+    ("I", "Integrator"),
+    ("C", "Colormap"),
+    ("A", "Auto range"),
+    ("9/0", "Color range"),
     ("-/+", "Speed"),
+    ("Shift -/+", "dt"),
     (".", "Step"),
+    (",", "Step back"),
+    ("Z", "Reverse time"),
+    ("LMB", "Disturb"),
+    ("[/]", "Radius"),
+    (";/'", "Amplitude"),
+    ("RMB", "Source"),
+    ("Wheel", "Zoom"),
+    ("MMB", "Pan"),
+    ("F", "Fit view"),
+    ("Shift LMB", "Cross-section"),
+    ("H/V", "Horizontal/vertical section"),
+    ("S", "Spatial spectrum"),
+    ("Ctrl LMB", "Probe"),
+    ("U", "Clear probes"),
+    ("E", "Export probes"),
+    ("K", "Source kind"),
+    ("X", "Clear sources"),
     ("Esc", "Quit"),
 ];
 
@@ -26,40 +59,55 @@ pub struct ControlPanel {}
 
 impl Observer for ControlPanel {
     fn render(&self, game: &crate::game::Game) {
-        let bar_y = screen_height() - BAR_HEIGHT;
-        draw_rectangle(0.0, bar_y, screen_width(), BAR_HEIGHT, LIGHTGRAY);
+        // Status on the top row, shortcuts wrapped onto as many rows as they need below it
+        let rows = Self::shortcut_rows(screen_width() - 2.0 * KEY_PADDING);
+        let bar_height = BAR_HEIGHT * (rows.len() + 1) as f32;
+        let bar_y = screen_height() - bar_height;
+        draw_rectangle(0.0, bar_y, screen_width(), bar_height, LIGHTGRAY);
 
-        let text_y = bar_y + BAR_HEIGHT / 2.0 + FONT_SIZE / 2.0 - 2.0;
+        let text_y = |row: usize| bar_y + BAR_HEIGHT * row as f32 + BAR_HEIGHT / 2.0 + FONT_SIZE / 2.0 - 2.0;
 
-        // Shortcuts on the left
-        let mut x = KEY_PADDING;
-        for (key, label) in SHORTCUTS {
-            let key_dims = measure_text(key, None, FONT_SIZE as u16, 1.0);
-            let label_dims = measure_text(label, None, FONT_SIZE as u16, 1.0);
-
-            let badge_w = key_dims.width + 8.0;
-            let badge_h = FONT_SIZE + 4.0;
-            let badge_y = bar_y + (BAR_HEIGHT - badge_h) / 2.0;
-            draw_rectangle(x, badge_y, badge_w, badge_h, DARKGRAY);
-            draw_text(key, x + 4.0, text_y, FONT_SIZE, WHITE);
-            x += badge_w + 5.0;
-
-            draw_text(label, x, text_y, FONT_SIZE, BLACK);
-            x += label_dims.width + ITEM_GAP;
-        }
-
-        // Status on the right
         let status = format!(
-            "{} | init: {} | speed: {}x",
+            "{}{}{}{} | init: {} {}x{} | boundary: {} | medium: {} | damping: {} | {} dt={} | speed: {}x | colors: {} {}",
             match game.state {
                 GameState::Running => "Running",
                 GameState::Paused => "Paused",
             },
-            game.init_variant.label(),
+            if game.recorder.is_some() { " (REC)" } else { "" },
+            if game.gif.is_some() { " (GIF)" } else { "" },
+            if game.reversed { " (reversed)" } else { "" },
+            game.scenario.as_ref().map_or(game.init_variant.label(), |scenario| scenario.name.as_str()),
+            game.grid_size().0,
+            game.grid_size().1,
+            game.config.boundary.label(),
+            game.config.medium.label(),
+            game.config.damping,
+            game.config.integrator.label(),
+            game.config.dt,
             game.updates_per_frame,
+            game.colors.colormap.label(),
+            if game.auto_range { "auto".to_string() } else { format!("±{}", short_number(game.colors.range.u)) },
         );
-        let status_dims = measure_text(&status, None, FONT_SIZE as u16, 1.0);
-        draw_text(&status, screen_width() - status_dims.width - KEY_PADDING, text_y, FONT_SIZE, BLACK);
+        draw_text(&status, KEY_PADDING, text_y(0), FONT_SIZE, BLACK);
+
+        for (row, shortcuts) in rows.iter().enumerate() {
+            let row_y = bar_y + BAR_HEIGHT * (row + 1) as f32;
+            let mut x = KEY_PADDING;
+            for (key, label) in shortcuts.iter() {
+                let key_dims = measure_text(key, None, FONT_SIZE as u16, 1.0);
+                let label_dims = measure_text(label, None, FONT_SIZE as u16, 1.0);
+
+                let badge_w = key_dims.width + 8.0;
+                let badge_h = FONT_SIZE + 4.0;
+                let badge_y = row_y + (BAR_HEIGHT - badge_h) / 2.0;
+                draw_rectangle(x, badge_y, badge_w, badge_h, DARKGRAY);
+                draw_text(key, x + 4.0, text_y(row + 1), FONT_SIZE, WHITE);
+                x += badge_w + 5.0;
+
+                draw_text(label, x, text_y(row + 1), FONT_SIZE, BLACK);
+                x += label_dims.width + ITEM_GAP;
+            }
+        }
     }
 
     fn update(&mut self, _game: &crate::game::Game) {}
@@ -69,4 +117,24 @@ impl ControlPanel {
     pub fn new() -> Self {
         Self {}
     }
+
+    /// Split `SHORTCUTS` into rows that each fit within `max_width`.
+    fn shortcut_rows(max_width: f32) -> Vec<&'static [(&'static str, &'static str)]> {
+        let mut rows = vec![];
+        let mut row_start = 0;
+        let mut x = 0.0;
+        for (i, (key, label)) in SHORTCUTS.iter().enumerate() {
+            let key_dims = measure_text(key, None, FONT_SIZE as u16, 1.0);
+            let label_dims = measure_text(label, None, FONT_SIZE as u16, 1.0);
+            let item_w = key_dims.width + 8.0 + 5.0 + label_dims.width;
+            if x + item_w > max_width && i > row_start {
+                rows.push(&SHORTCUTS[row_start..i]);
+                row_start = i;
+                x = 0.0;
+            }
+            x += item_w + ITEM_GAP;
+        }
+        rows.push(&SHORTCUTS[row_start..]);
+        rows
+    }
 }
@@ -1,19 +1,41 @@
-use std::time::{Duration, Instant};
+use std::time::Instant;
 
 use macroquad::prelude::*;
 
 use crate::game::Game;
-use crate::monitor::Monitor;
+use crate::monitor::{History, Monitor};
+use crate::probe::Sample;
+use crate::render::{nice_range, probe_color, short_number};
+use crate::spectrum::Spectrum;
 
 pub struct Debugger {
-    text: Vec<String>,
+    text: Vec<(String, Color)>,
     last_frame_instant: Instant,
     frame_time_monitor: Monitor,
     render_time_monitor: Monitor,
     update_time_monitor: Monitor,
+    total_energy: History,
+    kinetic_energy: History,
+    potential_energy: History,
+    /// step of the last sample, to notice when the field gets reset
+    last_step: u32,
+    /// spectrum of every probe's u, `None` until it has enough samples
+    spectra: Vec<Option<Spectrum>>,
+    /// frequency the field's fundamental mode should oscillate at, if it has a known one
+    fundamental: Option<f32>,
 }
 
 const WIDTH: f32 = 300.0;
+const ENERGY_HISTORY_LEN: usize = 256;
+const GRAPH_HEIGHT: f32 = 100.0;
+/// latest samples shown in the probe charts
+const PROBE_CHART_STEPS: usize = 512;
+const PROBE_CHART_HEIGHT: f32 = 70.0;
+const SPECTRUM_HEIGHT: f32 = 90.0;
+/// power range of the spectrum chart below its strongest bin
+const SPECTRUM_DECIBELS: f32 = 80.0;
+/// strongest peaks marked and listed for every probe
+const SPECTRUM_PEAKS: usize = 2;
 
 impl Debugger {
     pub fn new() -> Self {
@@ -23,22 +45,140 @@ impl Debugger {
             frame_time_monitor: Monitor::new(),
             render_time_monitor: Monitor::new(),
             update_time_monitor: Monitor::new(),
+            total_energy: History::new(ENERGY_HISTORY_LEN),
+            kinetic_energy: History::new(ENERGY_HISTORY_LEN),
+            potential_energy: History::new(ENERGY_HISTORY_LEN),
+            last_step: 0,
+            spectra: vec![],
+            fundamental: None,
         }
     }
     pub fn clear(&mut self) {
         self.text.clear();
     }
     pub fn println(&mut self, s: &str) {
-        self.text.push(s.to_string());
-        self.text.push("\n".to_string());
+        self.println_colored(s, BLACK);
+    }
+    pub fn warn(&mut self, s: &str) {
+        self.println_colored(s, RED);
+    }
+    fn println_colored(&mut self, s: &str, color: Color) {
+        self.text.push((s.to_string(), color));
+        self.text.push(("\n".to_string(), color));
+    }
+    /// Plot the energy histories, total in black, kinetic in red and potential in blue, on a
+    /// shared scale starting at zero.
+    fn draw_energy_graph(&self, top: f32) {
+        let left = Self::top_left().x + 15.0;
+        let width = WIDTH - 30.0;
+        draw_rectangle(left, top, width, GRAPH_HEIGHT, WHITE);
+        let max = self.total_energy.max().max(self.kinetic_energy.max()).max(self.potential_energy.max());
+        if max <= 0. {
+            return;
+        }
+        let dx = width / (self.total_energy.capacity() - 1) as f32;
+        for (history, color) in [
+            (&self.total_energy, BLACK),
+            (&self.kinetic_energy, RED),
+            (&self.potential_energy, BLUE),
+        ] {
+            let points: Vec<Vec2> = history
+                .iter()
+                .enumerate()
+                .map(|(i, e)| vec2(left + i as f32 * dx, top + GRAPH_HEIGHT * (1. - e / max)))
+                .collect();
+            for pair in points.windows(2) {
+                draw_line(pair[0].x, pair[0].y, pair[1].x, pair[1].y, 1.0, color);
+            }
+        }
+    }
+    /// Strip charts of the probes' latest samples, u above v, each on a symmetric scale fitting
+    /// all probes, with the newest sample on the right.
+    fn draw_probe_charts(game: &Game, top: f32) {
+        if game.probes.is_empty() {
+            return;
+        }
+        let left = Self::top_left().x + 15.0;
+        let width = WIDTH - 30.0;
+        let dx = width / (PROBE_CHART_STEPS - 1) as f32;
+        let probes: Vec<Vec<&Sample>> = game.probes.iter().map(|probe| probe.samples().collect()).collect();
+        let latest = |samples: &[&Sample]| samples.len().saturating_sub(PROBE_CHART_STEPS);
+        for (row, name) in ["u", "v"].into_iter().enumerate() {
+            let value = |sample: &Sample| if row == 0 { sample.u } else { sample.v };
+            let chart_top = top + row as f32 * (PROBE_CHART_HEIGHT + 5.0);
+            draw_rectangle(left, chart_top, width, PROBE_CHART_HEIGHT, WHITE);
+            let max = probes
+                .iter()
+                .flat_map(|samples| samples[latest(samples)..].iter().map(|sample| value(sample).abs()))
+                .fold(0., f32::max);
+            let range = nice_range(max);
+            let y = |value: f32| chart_top + PROBE_CHART_HEIGHT * (1. - value / range) / 2.;
+            draw_line(left, y(0.), left + width, y(0.), 1.0, LIGHTGRAY);
+            for (i, samples) in probes.iter().enumerate() {
+                let shown = &samples[latest(samples)..];
+                let start = left + width - (shown.len().max(1) - 1) as f32 * dx;
+                let points: Vec<Vec2> = shown
+                    .iter()
+                    .enumerate()
+                    .map(|(k, sample)| vec2(start + k as f32 * dx, y(value(sample))))
+                    .collect();
+                for pair in points.windows(2) {
+                    draw_line(pair[0].x, pair[0].y, pair[1].x, pair[1].y, 1.0, probe_color(i));
+                }
+            }
+            draw_text(&format!("{name} ±{}", short_number(range)), left + 4.0, chart_top + 14.0, 16.0, BLACK);
+        }
+    }
+    /// Power spectra of the probes' u in decibels below the strongest bin, over a logarithmic
+    /// frequency axis up to the Nyquist frequency of half a cycle per step. The strongest peaks
+    /// are marked, and the frequency of the fundamental mode drawn as a gray line.
+    fn draw_spectra(&self, top: f32) {
+        let Some(len) = self.spectra.iter().flatten().map(|spectrum| spectrum.len).max() else {
+            return;
+        };
+        let left = Self::top_left().x + 15.0;
+        let width = WIDTH - 30.0;
+        draw_rectangle(left, top, width, SPECTRUM_HEIGHT, WHITE);
+        let (low, high) = ((1. / len as f32).log10(), 0.5f32.log10());
+        let x = |frequency: f32| left + width * ((frequency.log10() - low) / (high - low)).clamp(0., 1.);
+        let max = self.spectra.iter().flatten().flat_map(|spectrum| spectrum.power[1..].iter().copied()).fold(0., f32::max);
+        if max <= 0. {
+            return;
+        }
+        let y = |power: f32| {
+            let decibels = 10. * (power.max(f32::MIN_POSITIVE) / max).log10();
+            top + SPECTRUM_HEIGHT * (-decibels / SPECTRUM_DECIBELS).clamp(0., 1.)
+        };
+        // a tick and label at every power of ten on the frequency axis
+        for exponent in low.ceil() as i32..=high.floor() as i32 {
+            let tick = x(10f32.powi(exponent));
+            draw_line(tick, top, tick, top + SPECTRUM_HEIGHT, 1.0, LIGHTGRAY);
+            draw_text(&short_number(10f32.powi(exponent)), tick + 2., top + SPECTRUM_HEIGHT - 4., 14., DARKGRAY);
+        }
+        if let Some(frequency) = self.fundamental {
+            draw_line(x(frequency), top, x(frequency), top + SPECTRUM_HEIGHT, 1.0, GRAY);
+        }
+        for (i, spectrum) in self.spectra.iter().enumerate() {
+            let Some(spectrum) = spectrum else {
+                continue;
+            };
+            let points: Vec<Vec2> = (1..spectrum.power.len())
+                .map(|bin| vec2(x(spectrum.frequency(bin)), y(spectrum.power[bin])))
+                .collect();
+            for pair in points.windows(2) {
+                draw_line(pair[0].x, pair[0].y, pair[1].x, pair[1].y, 1.0, probe_color(i));
+            }
+            for peak in spectrum.peaks(SPECTRUM_PEAKS) {
+                draw_circle_lines(x(peak.frequency), y(peak.power), 4., 1.5, probe_color(i));
+            }
+        }
+        draw_text(&format!("u power, {SPECTRUM_DECIBELS} dB"), left + 4.0, top + 14.0, 16.0, BLACK);
     }
     fn top_left() -> Vec2 {
         vec2(screen_width() - WIDTH, 0.0)
     }
 }
 
-const FRAME_RATE_WINDOW: usize = 120;
-
 impl crate::observer::Observer for Debugger {
     fn update(&mut self, game: &crate::game::Game) {
         if game.just_updated || self.text.is_empty() {
@@ -47,16 +187,96 @@ impl crate::observer::Observer for Debugger {
             self.frame_time_monitor.inc(self.last_frame_instant.elapsed().as_secs_f32());
 
             self.clear();
-            self.println(format!("Step: {}", game.step).as_str());
+            self.println(format!("Step: {}", game.field.step()).as_str());
             self.println(format!("Time Elapsed: {:.2}s", game.start_time.elapsed().as_millis() as f32 * 0.001).as_str());
             self.println(format!("Framerate: {:.0?}/s", 1. / self.frame_time_monitor.display_val).as_str());
-            self.println(format!("Render Time: {:.2}ms", self.render_time_monitor.display_val as f32 * 0.001).as_str());
-            self.println(format!("Update Time: {:.2}ms", self.update_time_monitor.display_val as f32 * 0.001).as_str());
-            
+            self.println(format!("Render Time: {:.2}ms", self.render_time_monitor.display_val * 0.001).as_str());
+            self.println(format!("Update Time: {:.2}ms", self.update_time_monitor.display_val * 0.001).as_str());
+            self.println(
+                format!(
+                    "Disturbance: r={:.0} a={:.0}",
+                    game.disturbance.radius, game.disturbance.amplitude
+                )
+                .as_str(),
+            );
+            let (cfl, cfl_limit) = (game.field.cfl(), game.field.cfl_limit());
+            self.println(format!("CFL: {:.3} (limit {:.3})", cfl, cfl_limit).as_str());
+            if cfl > cfl_limit {
+                self.warn("Unstable: lower dt or wave speed");
+            }
+            if game.reversed {
+                if let Some((step, error)) = game.origin_error() {
+                    self.println(format!("Time reversed: {:.2e} off the state at step {}", error, step).as_str());
+                }
+            }
+            if let Some(step) = game.diverged_at {
+                self.warn(format!("Diverged at step {}, paused", step).as_str());
+            }
+            if let Some(notice) = &game.notice {
+                self.println(notice);
+            }
+            if let Some(error) = &game.error {
+                // parse errors quote the offending line below the message, a few lines are enough
+                for line in error.lines().filter(|line| !line.trim().is_empty()).take(4) {
+                    self.warn(line);
+                }
+            }
+            if game.field.step() < self.last_step {
+                self.total_energy.clear();
+                self.kinetic_energy.clear();
+                self.potential_energy.clear();
+            }
+            self.last_step = game.field.step();
+            let diagnostics = game.field.diagnostics();
+            self.total_energy.push(diagnostics.total());
+            self.kinetic_energy.push(diagnostics.kinetic);
+            self.potential_energy.push(diagnostics.potential);
+            self.println(
+                format!(
+                    "Energy: {:.1} (drift {:+.3}%)",
+                    diagnostics.total(),
+                    diagnostics.drift * 100.
+                )
+                .as_str(),
+            );
+            self.println(
+                format!(
+                    "  kinetic {:.1}, potential {:.1}",
+                    diagnostics.kinetic, diagnostics.potential
+                )
+                .as_str(),
+            );
+            self.println(format!("u: L2 {:.1}, max {:.1}", diagnostics.l2, diagnostics.max).as_str());
+            self.println(format!("Sources ({} placed with RMB):", game.source_kind.label()).as_str());
+            for source in game.field.sources() {
+                self.println(format!("  {}", source.label()).as_str());
+            }
+            self.spectra = game
+                .probes
+                .iter()
+                .map(|probe| Spectrum::of(&probe.samples().map(|sample| sample.u).collect::<Vec<f32>>()))
+                .collect();
+            self.fundamental = game.field.fundamental_frequency();
+            if !game.probes.is_empty() {
+                if let Some(frequency) = self.fundamental {
+                    self.println(format!("Fundamental: {:.4e}/step ({:.0} steps)", frequency, 1. / frequency).as_str());
+                }
+            }
+            let peaks: Vec<Vec<String>> = self
+                .spectra
+                .iter()
+                .map(|spectrum| {
+                    let peaks = spectrum.as_ref().map(|spectrum| spectrum.peaks(SPECTRUM_PEAKS)).unwrap_or_default();
+                    peaks.iter().map(|peak| format!("{:.4e}", peak.frequency)).collect()
+                })
+                .collect();
+            for (i, peaks) in peaks.iter().enumerate().filter(|(_, peaks)| !peaks.is_empty()) {
+                self.println_colored(&format!("Probe {} peaks: {}/step", i + 1, peaks.join(", ")), probe_color(i));
+            }
             self.last_frame_instant = Instant::now();
         }
     }
-    fn render(&self, _game: &Game) {
+    fn render(&self, game: &Game) {
         const LEFT_MARGIN: f32 = 15.0;
         const TOP_MARGIN: f32 = 30.0;
         const LINE_HEIGHT: f32 = 10.0;
@@ -68,14 +288,18 @@ impl crate::observer::Observer for Debugger {
             screen_height() - 10.0,
             LIGHTGRAY,
         );
-        for (i, text) in self.text.iter().enumerate() {
+        for (i, (text, color)) in self.text.iter().enumerate() {
             draw_text(
                 text,
                 Self::top_left().x + LEFT_MARGIN,
-                TOP_MARGIN + i as f32 * LINE_HEIGHT as f32,
+                TOP_MARGIN + i as f32 * LINE_HEIGHT,
                 20.0,
-                BLACK,
+                *color,
             );
         }
+        let graph_top = TOP_MARGIN + self.text.len() as f32 * LINE_HEIGHT;
+        self.draw_energy_graph(graph_top);
+        Self::draw_probe_charts(game, graph_top + GRAPH_HEIGHT + 10.0);
+        self.draw_spectra(graph_top + GRAPH_HEIGHT + 10.0 + 2. * (PROBE_CHART_HEIGHT + 5.0));
     }
 }
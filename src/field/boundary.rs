@@ -0,0 +1,62 @@
+/// How waves behave when they reach the edge of the field.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum Boundary {
+    /// waves bounce back off the edges
+    Reflective,
+    /// waves leaving one edge re-enter on the opposite one
+    Periodic,
+    /// first-order Mur (Engquist-Majda) condition, lets waves leave the domain
+    Absorbing,
+    /// graded damping layer along the edges that soaks up outgoing waves
+    Sponge,
+}
+
+/// number of cells the sponge layer extends into the field
+const SPONGE_WIDTH: usize = 12;
+/// damping rate at the very edge of the sponge
+const SPONGE_STRENGTH: f32 = 0.1;
+
+/// Axes shorter than this keep reflective edges, since an absorbing edge on a strip a few
+/// cells wide would eat the wave travelling along it.
+pub const MIN_ABSORBING_CELLS: usize = 8;
+
+impl Boundary {
+    pub fn cycle(self) -> Self {
+        match self {
+            Self::Reflective => Self::Periodic,
+            Self::Periodic => Self::Absorbing,
+            Self::Absorbing => Self::Sponge,
+            Self::Sponge => Self::Reflective,
+        }
+    }
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Reflective => "reflective",
+            Self::Periodic => "periodic",
+            Self::Absorbing => "absorbing",
+            Self::Sponge => "sponge",
+        }
+    }
+}
+
+/// Coefficient of the first-order Mur condition for waves crossing `courant` cells per step.
+pub fn mur_coefficient(courant: f32) -> f32 {
+    (courant - 1.) / (courant + 1.)
+}
+
+/// Per-cell damping rate along an axis of `len` cells: 0 in the interior, rising
+/// quadratically towards the edges. Short axes get a proportionally thinner layer.
+pub fn sponge_profile(len: usize) -> Vec<f32> {
+    let width = if len < MIN_ABSORBING_CELLS { 0 } else { SPONGE_WIDTH.min(len / 4) };
+    (0..len)
+        .map(|i| {
+            let depth = i.min(len - 1 - i);
+            if depth < width {
+                let t = (width - depth) as f32 / width as f32;
+                SPONGE_STRENGTH * t * t
+            } else {
+                0.
+            }
+        })
+        .collect()
+}
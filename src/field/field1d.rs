@@ -1,50 +1,167 @@
+use ndarray::prelude::*;
+use ndarray::ShapeError;
 use std::f32::consts::PI;
 
-use macroquad::prelude::*;
+use super::boundary::{mur_coefficient, sponge_profile, MIN_ABSORBING_CELLS};
+use super::{Boundary, Diagnostics, Disturbance, Field, FieldConfig, Integrator, Medium, Source};
 
-use super::{Field, Pixel};
+const PROPAGATION_SPEED: f32 = 0.05;
+/// number of cells the presets were designed at
+const DEFAULT_WIDTH: usize = 256;
+/// smallest width, the stencil needs an interior cell between the edges
+const MIN_WIDTH: usize = 3;
 
 pub struct Field1D {
-    pixels: Vec<Vec<Pixel>>,
+    u: Array1<f32>,
+    v: Array1<f32>,
+    boundary: Boundary,
+    /// local c^2 of every cell
+    c2: Array1<f32>,
+    /// fraction of the velocity lost per unit time, everywhere
+    damping: f32,
+    /// additional per-cell damping from the medium
+    loss: Array1<f32>,
+    /// damping of the sponge layer, only used with `Boundary::Sponge`
+    sponge: Array1<f32>,
+    /// refractive index of every cell relative to the medium preset, e.g. from a scenario
+    index: Array1<f32>,
+    integrator: Integrator,
+    dt: f32,
+    /// total energy at step 0, the reference for the energy drift
+    initial_energy: f32,
+    sources: Vec<Source>,
+    step: u32,
 }
 
 impl Field for Field1D {
-    fn render(&mut self) {
-        let pixel_width = 1.5;
-        let pixel_height = 40.0;
-        let offset_x = 50.0;
-        let offset_y = 50.0;
-
-        for j in 0..self.width() {
-            let u = self.pixels[0][j].u;
-            let red = u as u8;
-            let green = (-u) as u8;
-            draw_rectangle(
-                offset_x + pixel_width * j as f32,
-                offset_y,
-                pixel_width,
-                pixel_height,
-                Color::from_rgba(red, green, 0, 255),
-            );
-        }
-    }
     fn update(&mut self) {
-        let mut field_deltas = vec![vec![0f32; self.width()]; self.height()];
-        for j in 0..self.width() {
-            field_deltas[0][j] += self.force(j, j as i32 + 1);
-            field_deltas[0][j] += self.force(j, j as i32 - 1);
+        let absorbing = self.boundary == Boundary::Absorbing && self.width() >= MIN_ABSORBING_CELLS;
+        let u_prev = absorbing.then(|| self.u.clone());
+
+        // move the state out so the integrator can borrow it alongside `self`
+        let mut u = std::mem::take(&mut self.u);
+        let mut v = std::mem::take(&mut self.v);
+        self.integrator
+            .step(&mut u, &mut v, self.dt, |u, v| self.acceleration(u, v));
+        self.u = u;
+        self.v = v;
+
+        if let Some(u_prev) = u_prev {
+            self.apply_mur(&u_prev);
         }
-        // update volocities
-        for i in 0..self.height() {
-            for j in 0..self.width() {
-                self.pixels[i][j].v += field_deltas[i][j];
-            }
+        self.step += 1;
+        self.apply_sources();
+    }
+
+    fn update_backwards(&mut self) {
+        let mut u = std::mem::take(&mut self.u);
+        let mut v = std::mem::take(&mut self.v);
+        self.integrator
+            .step_back(&mut u, &mut v, self.dt, |u, v| self.acceleration(u, v));
+        self.u = u;
+        self.v = v;
+        self.step = self.step.saturating_sub(1);
+    }
+
+    fn configure(&mut self, config: FieldConfig) {
+        self.boundary = config.boundary;
+        self.c2 = Self::c2_from_medium(config.medium, &self.index);
+        self.loss = Self::loss_from_medium(self.width(), config.medium);
+        self.damping = config.damping;
+        self.integrator = config.integrator;
+        self.dt = config.dt;
+    }
+
+    fn cfl(&self) -> f32 {
+        let c2_max = self.c2.iter().cloned().fold(0., f32::max);
+        c2_max.sqrt() * self.dt
+    }
+
+    fn cfl_limit(&self) -> f32 {
+        self.integrator.cfl_limit(1)
+    }
+
+    fn fundamental_frequency(&self) -> Option<f32> {
+        let c2 = self.c2[0];
+        let uniform = self.c2.iter().all(|&x| x == c2) && self.loss.iter().all(|&x| x == 0.);
+        if self.boundary != Boundary::Reflective || self.damping != 0. || !uniform {
+            return None;
         }
-        // update values
-        for i in 0..self.height() {
-            for j in 0..self.width() {
-                self.pixels[i][j].u += self.pixels[i][j].v;
-            }
+        let k = PI / self.width() as f32;
+        Some(super::wave_frequency(c2, k, &[(1, 1.)], self.integrator, self.dt))
+    }
+
+    fn diagnostics(&self) -> Diagnostics {
+        let potential = self.potential_energy();
+        let mut diagnostics = super::state_diagnostics(&self.u, &self.v);
+        diagnostics.potential = potential;
+        diagnostics.drift = super::drift(diagnostics.total(), self.initial_energy);
+        diagnostics
+    }
+
+    fn diverged(&self) -> bool {
+        super::diverged(&self.u, &self.v)
+    }
+
+    fn step(&self) -> u32 {
+        self.step
+    }
+
+    fn set_step(&mut self, step: u32) {
+        self.step = step;
+    }
+
+    fn add_source(&mut self, source: Source) {
+        self.sources.push(source);
+    }
+
+    fn sources(&self) -> &[Source] {
+        &self.sources
+    }
+
+    fn clear_sources(&mut self) {
+        self.sources.clear();
+    }
+
+    fn u(&self) -> ArrayViewD<'_, f32> {
+        self.u.view().into_dyn()
+    }
+
+    fn v(&self) -> ArrayViewD<'_, f32> {
+        self.v.view().into_dyn()
+    }
+
+    fn c2(&self) -> ArrayViewD<'_, f32> {
+        self.c2.view().into_dyn()
+    }
+
+    fn index(&self) -> ArrayViewD<'_, f32> {
+        self.index.view().into_dyn()
+    }
+
+    fn mask(&self) -> Option<ArrayViewD<'_, bool>> {
+        None
+    }
+
+    fn set_state(&mut self, u: ArrayD<f32>, v: ArrayD<f32>) -> Result<(), ShapeError> {
+        (self.u, self.v) = super::fit_state(u, v, self.u.shape())?;
+        self.initial_energy = self.diagnostics().total();
+        Ok(())
+    }
+
+    fn restore_state(&mut self, u: ArrayD<f32>, v: ArrayD<f32>, step: u32) -> Result<(), ShapeError> {
+        (self.u, self.v) = super::fit_state(u, v, self.u.shape())?;
+        self.step = step;
+        Ok(())
+    }
+
+    fn add_disturbance(&mut self, cell: (f32, f32), disturbance: Disturbance) {
+        let (x, _) = cell;
+        let j_min = (x - disturbance.radius).floor().max(0.) as usize;
+        let j_max = ((x + disturbance.radius).ceil() as usize).min(self.width());
+        for j in j_min..j_max {
+            let distance = (j as f32 + 0.5 - x).abs();
+            self.u[j] += disturbance.displacement(distance);
         }
     }
 }
@@ -62,6 +179,10 @@ impl Field1DInit {
             Self::Centered => Self::AtEnd,
         }
     }
+    /// Width the preset was designed at, used unless another one is asked for.
+    pub fn default_width(self) -> usize {
+        DEFAULT_WIDTH
+    }
     pub fn label(self) -> &'static str {
         match self {
             Self::AtEnd => "at end",
@@ -71,47 +192,135 @@ impl Field1DInit {
 }
 
 impl Field1D {
-    pub fn new(init: Field1DInit) -> Self {
+    /// A field of `width` cells set up as `init`, with the preset scaled to fit.
+    pub fn new(init: Field1DInit, width: usize, config: FieldConfig) -> Self {
+        let width = width.max(MIN_WIDTH);
+        let (u, v) = match init {
+            Field1DInit::AtEnd => Self::pixels_at_end(width),
+            Field1DInit::Centered => Self::pixels_centered(width),
+        };
+        let mut field = Self::from_index(Array1::ones(width), config);
+        field.u = u;
+        field.v = v;
+        field.initial_energy = field.diagnostics().total();
+        field
+    }
+
+    /// A field at rest with a refractive `index` per cell on top of the medium preset, which
+    /// also gives the width of the field.
+    pub fn from_index(index: Array1<f32>, config: FieldConfig) -> Self {
+        let width = index.len();
         Self {
-            pixels: match init {
-                Field1DInit::AtEnd => Self::pixels_at_end(),
-                Field1DInit::Centered => Self::pixels_centered(),
-            },
+            u: Array1::zeros(width),
+            v: Array1::zeros(width),
+            boundary: config.boundary,
+            c2: Self::c2_from_medium(config.medium, &index),
+            damping: config.damping,
+            loss: Self::loss_from_medium(width, config.medium),
+            sponge: Array1::from(sponge_profile(width)),
+            index,
+            integrator: config.integrator,
+            dt: config.dt,
+            initial_energy: 0.,
+            sources: vec![],
+            step: 0,
         }
     }
-    fn pixels_centered() -> Vec<Vec<Pixel>> {
-        let mut pixels = vec![vec![Pixel::zero(); 256]];
-        // let half = pixels[0].len() / 2;
-        for j in 120..136 {
-            pixels[0][j] = Pixel { u: 255.0, v: 0.0 };
+    /// A plateau over the middle sixteenth of the field.
+    fn pixels_centered(width: usize) -> (Array1<f32>, Array1<f32>) {
+        let mut u = Array1::zeros(width);
+        let half = (width / 32).max(1);
+        for j in width / 2 - half..width / 2 + half {
+            u[j] = 255.0;
         }
-        pixels
-    }
-    fn pixels_at_end() -> Vec<Vec<Pixel>> {
-        let mut pixels = vec![vec![Pixel::zero(); 256]];
-        // let half = pixels[0].len() / 2;
-        for j in 0..20 {
-            pixels[0][j] = Pixel {
-                u: (j as f32 * PI * 0.025).cos() * 255.0,
-                v: 0.0,
-            };
+        (u, Array1::zeros(width))
+    }
+    /// A quarter cosine falling off over the first 20 of every 256 cells.
+    fn pixels_at_end(width: usize) -> (Array1<f32>, Array1<f32>) {
+        let mut u = Array1::zeros(width);
+        let len = (20 * width / DEFAULT_WIDTH).max(1);
+        for j in 0..len {
+            u[j] = (j as f32 / len as f32 * PI / 2.).cos() * 255.0;
+        }
+        (u, Array1::zeros(width))
+    }
+
+    /// dv/dt for the state `(u, v)`: the wave equation's c^2 times the laplacian, minus damping.
+    fn acceleration(&self, u: &mut Array1<f32>, v: &Array1<f32>) -> Array1<f32> {
+        let mut a = Array1::zeros(u.len());
+        for j in 0..u.len() {
+            a[j] += self.force(u, j, j as i32 + 1);
+            a[j] += self.force(u, j, j as i32 - 1);
+
+            let sponge = if self.boundary == Boundary::Sponge { self.sponge[j] } else { 0. };
+            a[j] -= (self.damping + self.loss[j] + sponge) * v[j];
         }
-        pixels
+        a
     }
 
     // attempt to simulate the second derivative
-    fn force(&self, target: usize, source: i32) -> f32 {
-        if source < 0 || source >= self.width() as i32 {
+    fn force(&self, u: &Array1<f32>, target: usize, source: i32) -> f32 {
+        let source = if self.boundary == Boundary::Periodic {
+            source.rem_euclid(u.len() as i32)
+        } else {
+            source
+        };
+        if source < 0 || source >= u.len() as i32 {
             return 0f32;
         }
         let source = source as usize;
         // c^2
-        (0.05) * (self.pixels[0][source].u - self.pixels[0][target].u)
+        self.c2[target] * (u[source] - u[target])
     }
-    fn width(&self) -> usize {
-        self.pixels[0].len()
+    /// Sum of `c^2 (du/dx)^2 / 2` over forward differences, wrapping around when periodic.
+    fn potential_energy(&self) -> f32 {
+        let width = self.width();
+        let periodic = self.boundary == Boundary::Periodic;
+        (0..width)
+            .filter(|&j| j + 1 < width || periodic)
+            .map(|j| self.c2[j] * (self.u[(j + 1) % width] - self.u[j]).powi(2) / 2.)
+            .sum()
     }
-    fn height(&self) -> usize {
-        self.pixels.len()
+
+    fn c2_from_medium(medium: Medium, index: &Array1<f32>) -> Array1<f32> {
+        let width = index.len();
+        Array1::from_shape_fn(width, |j| {
+            PROPAGATION_SPEED * medium.relative_c2((j as f32 + 0.5) / width as f32, 0.5) / (index[j] * index[j])
+        })
+    }
+
+    fn loss_from_medium(width: usize, medium: Medium) -> Array1<f32> {
+        Array1::from_shape_fn(width, |j| medium.damping((j as f32 + 0.5) / width as f32, 0.5))
+    }
+
+    /// Impose the displacement of every active source on its cells. Only the x coordinate of
+    /// a source's cells matters here, so a vertical line drives a single cell.
+    fn apply_sources(&mut self) {
+        for source in &self.sources {
+            let Some(value) = source.value(self.step) else {
+                continue;
+            };
+            for (j, _) in source.cells() {
+                if let Some(u) = self.u.get_mut(j) {
+                    self.v[j] += (value - *u) / self.dt;
+                    *u = value;
+                }
+            }
+        }
+    }
+
+    /// Overwrite both ends with the first-order Mur condition
+    /// `u_edge' = u_inner + k (u_inner' - u_edge)`, which only lets waves travel outwards.
+    fn apply_mur(&mut self, u_prev: &Array1<f32>) {
+        let width = self.width();
+        for (edge, inner) in [(0, 1), (width - 1, width - 2)] {
+            let k = mur_coefficient(self.c2[edge].sqrt() * self.dt);
+            let u = u_prev[inner] + k * (self.u[inner] - u_prev[edge]);
+            self.v[edge] = (u - u_prev[edge]) / self.dt;
+            self.u[edge] = u;
+        }
+    }
+    fn width(&self) -> usize {
+        self.u.len()
     }
 }
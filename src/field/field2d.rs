@@ -1,124 +1,197 @@
 use ndarray::prelude::*;
+use ndarray::ShapeError;
 use std::f32::consts::PI;
 
-use macroquad::prelude::*;
-
-use super::{Field, Pixel};
+use super::boundary::{mur_coefficient, sponge_profile, MIN_ABSORBING_CELLS};
+use super::{Boundary, Diagnostics, Disturbance, Field, FieldConfig, Integrator, Medium, Source, SourceShape, Waveform};
 
 const PROPAGATION_SPEED: f32 = 0.01;
+/// size of the slit presets, large enough to resolve the interference pattern
+const SLIT_FIELD_SIZE: usize = 128;
+/// wavelength of the plane wave sent at the slits, in cells
+const SLIT_WAVELENGTH: f32 = 8.;
+/// displacement of the line source driving the slit presets
+const SLIT_AMPLITUDE: f32 = 255.;
+/// shortest wavelength the slit presets shrink to, below which the grid distorts the wave
+const MIN_WAVELENGTH: f32 = 4.;
+/// smallest width or height, the stencil needs an interior cell between the edges
+const MIN_SIZE: usize = 3;
 
 pub struct Field2D {
     u: Array2<f32>,
     v: Array2<f32>,
     vectorized: bool,
-    field_image: Image,
-    field_texture: Option<Texture2D>,
-    graph_image: Image,
-    graph_texture: Option<Texture2D>,
+    boundary: Boundary,
+    /// local c^2 of every cell
+    c2: Array2<f32>,
+    /// fraction of the velocity lost per unit time, everywhere
+    damping: f32,
+    /// additional per-cell damping from the medium
+    loss: Array2<f32>,
+    /// damping of the sponge layer, only used with `Boundary::Sponge`
+    sponge: Array2<f32>,
+    /// refractive index of every cell relative to the medium preset, e.g. from a scenario
+    index: Array2<f32>,
+    integrator: Integrator,
+    dt: f32,
+    /// total energy at step 0, the reference for the energy drift
+    initial_energy: f32,
+    /// obstacle cells, pinned to zero every step
+    mask: Array2<bool>,
+    sources: Vec<Source>,
+    /// wavelength of the plane wave the slit presets are driven with, to retune its source to
+    /// the frequency that wavelength takes under new settings
+    slit_wavelength: Option<f32>,
+    step: u32,
 }
 
 impl Field for Field2D {
-    fn render(&mut self) {
-        let offset_x = 50.0;
-        let offset_y = 50.0;
-
-        for y in 0..self.height() {
-            for x in 0..self.width() {
-                let u = *self.u.get((x, y)).unwrap();
-                let red = u as u8;
-                let green = (-u) as u8;
-                self.field_image.set_pixel(x as u32, y as u32, Color::from_rgba(red, green, 0, 255));
-            }
-        }
-        let field_texture = self.field_texture.get_or_insert_with(|| Texture2D::from_image(&self.field_image));
-        field_texture.update(&self.field_image);
-        draw_texture_ex(
-            field_texture,
-            offset_x,
-            offset_y,
-            WHITE,
-            DrawTextureParams {
-                dest_size: Some(vec2(400., 400.)),
-                ..Default::default()
-            },
-        );
-
-        let center_y = self.height() / 2;
-        let graph_h = self.graph_image.height as u32;
-        // clear graph
-        for x in 0..self.width() {
-            for gy in 0..graph_h {
-                self.graph_image.set_pixel(x as u32, gy, BLACK);
-            }
-        }
-        for x in 0..self.width() {
-            let yx = (*self.u.get((x, center_y)).unwrap() / 7.1 + 64.).clamp(0., (graph_h - 1) as f32) as u32;
-            let yv = ((*self.v.get((x, center_y)).unwrap() * 16.) + 64.).clamp(0., (graph_h - 1) as f32) as u32;
-            self.graph_image.set_pixel(x as u32, yx, RED);
-            self.graph_image.set_pixel(x as u32, yv, GREEN);
-        }
-        let graph_texture = self.graph_texture.get_or_insert_with(|| Texture2D::from_image(&self.graph_image));
-        graph_texture.update(&self.graph_image);
-        draw_texture_ex(
-            graph_texture,
-            offset_x,
-            offset_y + 450.,
-            WHITE,
-            DrawTextureParams {
-                dest_size: Some(vec2(400., 400.)),
-                ..Default::default()
-            },
-        );
+    fn update(&mut self) {
+        let u_prev = (self.boundary == Boundary::Absorbing).then(|| self.u.clone());
+
+        // move the state out so the integrator can borrow it alongside `self`
+        let mut u = std::mem::take(&mut self.u);
+        let mut v = std::mem::take(&mut self.v);
+        self.integrator
+            .step(&mut u, &mut v, self.dt, |u, v| self.acceleration(u, v));
+        self.u = u;
+        self.v = v;
+
+        if let Some(u_prev) = u_prev {
+            self.apply_mur(&u_prev);
+        }
+        self.step += 1;
+        self.apply_sources();
+        self.apply_mask();
     }
 
-    fn update(&mut self) {
-        let mut field_deltas = vec![vec![0f32; self.height()]; self.width()];
+    fn update_backwards(&mut self) {
+        let mut u = std::mem::take(&mut self.u);
+        let mut v = std::mem::take(&mut self.v);
+        self.integrator
+            .step_back(&mut u, &mut v, self.dt, |u, v| self.acceleration(u, v));
+        self.u = u;
+        self.v = v;
+        self.step = self.step.saturating_sub(1);
+        self.apply_mask();
+    }
 
-        if self.vectorized {
-            // the following 4 updates ensure reflective boundaries
-            let r = 1. * &self.u.slice(s![1..-1, -2..-1]);
-            r.assign_to(self.u.slice_mut(s![1..-1, -1..]));
+    fn configure(&mut self, config: FieldConfig) {
+        // the slit source as tuned to the old settings, to find it among the sources
+        let slit_source = self.slit_wavelength.map(|wavelength| self.plane_wave_source(wavelength));
+        self.boundary = config.boundary;
+        self.c2 = Self::c2_from_medium(config.medium, &self.index);
+        self.loss = Self::loss_from_medium(self.width(), self.height(), config.medium);
+        self.damping = config.damping;
+        self.integrator = config.integrator;
+        self.dt = config.dt;
+        if let (Some(old), Some(wavelength)) = (slit_source, self.slit_wavelength) {
+            let retuned = self.plane_wave_source(wavelength);
+            for source in self.sources.iter_mut().filter(|source| **source == old) {
+                *source = retuned;
+            }
+        }
+    }
 
-            let r = 1. * &self.u.slice(s![1..-1, 1..2]);
-            r.assign_to(self.u.slice_mut(s![1..-1, ..1]));
+    fn cfl(&self) -> f32 {
+        let c2_max = self.c2.iter().cloned().fold(0., f32::max);
+        c2_max.sqrt() * self.dt
+    }
 
-            let r = 1. * &self.u.slice(s![-2..-1, 1..-1]);
-            r.assign_to(self.u.slice_mut(s![-1.., 1..-1]));
+    fn cfl_limit(&self) -> f32 {
+        self.integrator.cfl_limit(2)
+    }
 
-            let r = 1. * &self.u.slice(s![1..2, 1..-1]);
-            r.assign_to(self.u.slice_mut(s![..1, 1..-1]));
+    fn fundamental_frequency(&self) -> Option<f32> {
+        let c2 = self.c2[(0, 0)];
+        let uniform = self.c2.iter().all(|&x| x == c2) && self.loss.iter().all(|&x| x == 0.);
+        if self.boundary != Boundary::Reflective || self.damping != 0. || !uniform || self.mask.iter().any(|&m| m) {
+            return None;
+        }
+        // the vectorized path copies the edge cells from their neighbours, so the wave spans the
+        // cells in between; the loop path reflects off the outer faces of the edge cells
+        let length = if self.vectorized { self.width() - 2 } else { self.width() };
+        Some(super::wave_frequency(c2, PI / length as f32, self.stencil(), self.integrator, self.dt))
+    }
 
-            let result = &self.v.slice(s![1..-1, 1..-1])
-                + (PROPAGATION_SPEED
-                    * &(-4. * &self.u.slice(s![1..-1, 1..-1])
-                        + &self.u.slice(s![2.., 1..-1])
-                        + &self.u.slice(s![..-2, 1..-1])
-                        + &self.u.slice(s![1..-1, 2..])
-                        + &self.u.slice(s![1..-1, ..-2])));
+    fn diagnostics(&self) -> Diagnostics {
+        let potential = self.potential_energy();
+        let mut diagnostics = super::state_diagnostics(&self.u, &self.v);
+        diagnostics.potential = potential;
+        diagnostics.drift = super::drift(diagnostics.total(), self.initial_energy);
+        diagnostics
+    }
 
-            result.assign_to(self.v.slice_mut(s![1..-1, 1..-1]));
-        } else {
-            for x in 0..self.width() {
-                for y in 0..self.height() {
-                    field_deltas[x][y] += self.force((x, y), (x as i32 + 1, y as i32));
-                    field_deltas[x][y] += self.force((x, y), (x as i32 - 1, y as i32));
-                    field_deltas[x][y] += self.force((x, y), (x as i32, y as i32 - 1));
-                    field_deltas[x][y] += self.force((x, y), (x as i32, y as i32 + 1));
-
-                    field_deltas[x][y] += 0.0625 * self.force((x, y), (x as i32, y as i32 + 2));
-                    field_deltas[x][y] += 0.0625 * self.force((x, y), (x as i32, y as i32 - 2));
-                    field_deltas[x][y] += 0.0625 * self.force((x, y), (x as i32 + 2, y as i32));
-                    field_deltas[x][y] += 0.0625 * self.force((x, y), (x as i32 - 2, y as i32));
-                }
-            }
-            for y in 0..self.height() {
-                for x in 0..self.width() {
-                    *self.v.get_mut((x, y)).unwrap() += field_deltas[x][y];
-                }
+    fn diverged(&self) -> bool {
+        super::diverged(&self.u, &self.v)
+    }
+
+    fn step(&self) -> u32 {
+        self.step
+    }
+
+    fn set_step(&mut self, step: u32) {
+        self.step = step;
+    }
+
+    fn add_source(&mut self, source: Source) {
+        self.sources.push(source);
+    }
+
+    fn sources(&self) -> &[Source] {
+        &self.sources
+    }
+
+    fn clear_sources(&mut self) {
+        self.sources.clear();
+    }
+
+    fn u(&self) -> ArrayViewD<'_, f32> {
+        self.u.view().into_dyn()
+    }
+
+    fn v(&self) -> ArrayViewD<'_, f32> {
+        self.v.view().into_dyn()
+    }
+
+    fn c2(&self) -> ArrayViewD<'_, f32> {
+        self.c2.view().into_dyn()
+    }
+
+    fn index(&self) -> ArrayViewD<'_, f32> {
+        self.index.view().into_dyn()
+    }
+
+    fn mask(&self) -> Option<ArrayViewD<'_, bool>> {
+        Some(self.mask.view().into_dyn())
+    }
+
+    fn set_state(&mut self, u: ArrayD<f32>, v: ArrayD<f32>) -> Result<(), ShapeError> {
+        (self.u, self.v) = super::fit_state(u, v, self.u.shape())?;
+        self.initial_energy = self.diagnostics().total();
+        Ok(())
+    }
+
+    fn restore_state(&mut self, u: ArrayD<f32>, v: ArrayD<f32>, step: u32) -> Result<(), ShapeError> {
+        (self.u, self.v) = super::fit_state(u, v, self.u.shape())?;
+        self.step = step;
+        Ok(())
+    }
+
+    fn add_disturbance(&mut self, cell: (f32, f32), disturbance: Disturbance) {
+        let (cx, cy) = cell;
+        // only visit the cells the bump can reach
+        let x_min = (cx - disturbance.radius).floor().max(0.) as usize;
+        let y_min = (cy - disturbance.radius).floor().max(0.) as usize;
+        let x_max = ((cx + disturbance.radius).ceil() as usize).min(self.width());
+        let y_max = ((cy + disturbance.radius).ceil() as usize).min(self.height());
+        for x in x_min..x_max {
+            for y in y_min..y_max {
+                // measure from the center of the cell
+                let distance = (x as f32 + 0.5 - cx).hypot(y as f32 + 0.5 - cy);
+                *self.u.get_mut((x, y)).unwrap() += disturbance.displacement(distance);
             }
         }
-
-        self.u = &self.u + &self.v;
     }
 }
 
@@ -128,6 +201,9 @@ pub enum Field2DInit {
     Centered,
     Traveling,
     Standing,
+    SingleSlit,
+    DoubleSlit,
+    Grating,
 }
 
 impl Field2DInit {
@@ -136,7 +212,18 @@ impl Field2DInit {
             Self::Zero => Self::Centered,
             Self::Centered => Self::Traveling,
             Self::Traveling => Self::Standing,
-            Self::Standing => Self::Zero,
+            Self::Standing => Self::SingleSlit,
+            Self::SingleSlit => Self::DoubleSlit,
+            Self::DoubleSlit => Self::Grating,
+            Self::Grating => Self::Zero,
+        }
+    }
+    /// Size the preset was designed at, used unless another one is asked for.
+    pub fn default_size(self) -> (usize, usize) {
+        match self {
+            Self::Zero | Self::Traveling | Self::Standing => (64, 3),
+            Self::Centered => (64, 64),
+            Self::SingleSlit | Self::DoubleSlit | Self::Grating => (SLIT_FIELD_SIZE, SLIT_FIELD_SIZE),
         }
     }
     pub fn label(self) -> &'static str {
@@ -145,39 +232,96 @@ impl Field2DInit {
             Self::Centered => "centered",
             Self::Traveling => "traveling",
             Self::Standing => "standing",
+            Self::SingleSlit => "single slit",
+            Self::DoubleSlit => "double slit",
+            Self::Grating => "grating",
         }
     }
 }
 
 impl Field2D {
-    pub fn new(vectorized: bool, init: Field2DInit) -> Self {
+    /// A field of `size` cells (width, height) set up as `init`, with the preset scaled to fit.
+    pub fn new(vectorized: bool, init: Field2DInit, size: (usize, usize), config: FieldConfig) -> Self {
+        let (width, height) = (size.0.max(MIN_SIZE), size.1.max(MIN_SIZE));
+        // the slit presets keep their proportions to the height they were designed at
+        let slit_scale = |cells: usize| ((cells * height) as f32 / SLIT_FIELD_SIZE as f32).round().max(1.) as usize;
         let pixels = match init {
-            Field2DInit::Zero => Self::zero(64, 3),
-            Field2DInit::Centered => Self::pixels_centered(64, 64),
-            Field2DInit::Traveling => Self::traveling(64, 3),
-            Field2DInit::Standing => Self::standing(64, 3),
+            Field2DInit::Centered => Self::pixels_centered(width, height),
+            Field2DInit::Traveling => Self::traveling(width, height),
+            Field2DInit::Standing => Self::standing(width, height),
+            // the slit presets start at rest, driven by a source added below
+            _ => Self::zero(width, height),
         };
-        let width = pixels.0.shape()[0];
-        let height = pixels.0.shape()[1];
-        Self {
-            u: pixels.0,
-            v: pixels.1,
-            vectorized,
-            field_image: Image::gen_image_color(width as u16, height as u16, WHITE),
-            field_texture: None,
-            graph_image: Image::gen_image_color(width as u16, 128, BLACK),
-            graph_texture: None,
+        let mask = match init {
+            Field2DInit::SingleSlit => Self::wall_with_slits(width, height, &[height / 2], slit_scale(6)),
+            Field2DInit::DoubleSlit => {
+                let offset = slit_scale(10);
+                let slits = [height / 2 - offset.min(height / 2), height / 2 + offset];
+                Self::wall_with_slits(width, height, &slits, slit_scale(4))
+            }
+            Field2DInit::Grating => {
+                let slits: Vec<usize> = (slit_scale(4)..height).step_by(slit_scale(12)).collect();
+                Self::wall_with_slits(width, height, &slits, slit_scale(3))
+            }
+            _ => Array2::from_elem((width, height), false),
+        };
+        let mut field = Self::from_maps(vectorized, Array2::ones((width, height)), mask, config)
+            .expect("the presets' masks cover the grid");
+        field.u = pixels.0;
+        field.v = pixels.1;
+        field.initial_energy = field.diagnostics().total();
+        if matches!(init, Field2DInit::SingleSlit | Field2DInit::DoubleSlit | Field2DInit::Grating) {
+            let wavelength = (SLIT_WAVELENGTH * height as f32 / SLIT_FIELD_SIZE as f32).max(MIN_WAVELENGTH);
+            field.add_source(field.plane_wave_source(wavelength));
+            field.slit_wavelength = Some(wavelength);
         }
+        field
     }
 
-    fn add_disturbance(_mouse_pos: Vec2) {
-        let _pixels_centered = Self::pixels_centered(64, 64);
+    /// A field at rest with a refractive `index` per cell on top of the medium preset and the
+    /// obstacle cells in `mask`, both also giving the size of the field. Fails if they differ in
+    /// shape.
+    pub fn from_maps(
+        vectorized: bool,
+        index: Array2<f32>,
+        mask: Array2<bool>,
+        config: FieldConfig,
+    ) -> Result<Self, String> {
+        if index.dim() != mask.dim() {
+            return Err(format!("index {:?} and mask {:?} differ in shape", index.dim(), mask.dim()));
+        }
+        let (width, height) = index.dim();
+        let sponge_x = sponge_profile(width);
+        let sponge_y = sponge_profile(height);
+        Ok(Self {
+            u: Array2::zeros((width, height)),
+            v: Array2::zeros((width, height)),
+            vectorized,
+            boundary: config.boundary,
+            c2: Self::c2_from_medium(config.medium, &index),
+            damping: config.damping,
+            loss: Self::loss_from_medium(width, height, config.medium),
+            // combine the layers along both axes as if applied one after the other
+            sponge: Array2::from_shape_fn((width, height), |(x, y)| {
+                1. - (1. - sponge_x[x]) * (1. - sponge_y[y])
+            }),
+            index,
+            integrator: config.integrator,
+            dt: config.dt,
+            initial_energy: 0.,
+            mask,
+            sources: vec![],
+            slit_wavelength: None,
+            step: 0,
+        })
     }
 
     fn pixels_centered(width: usize, height: usize) -> (Array2<f32>, Array2<f32>) {
-        let center = vec2(width as f32 / 2., height as f32 / 2.);
+        let (cx, cy) = (width as f32 / 2., height as f32 / 2.);
+        // the bump spans a quarter of the shorter side, as on the 64x64 preset
+        let scale = width.min(height) as f32 / 64.;
         let f = |x: usize, y: usize| {
-            let distance = center.distance(vec2(x as f32, y as f32)) * 0.1;
+            let distance = (x as f32 - cx).hypot(y as f32 - cy) * 0.1 / scale;
             if distance <= PI / 2. {
                 255. * distance.cos()
             } else {
@@ -190,8 +334,8 @@ impl Field2D {
         Self::pixels_from_fn(width, height, |_, _| 0., |_, _| 0.)
     }
     fn traveling(width: usize, height: usize) -> (Array2<f32>, Array2<f32>) {
-        let f = |x: usize, y: usize| {
-            let d = (x as f32 - (width as f32 / 2.0)) / 10.0;
+        let f = |x: usize, _y: usize| {
+            let d = (x as f32 - (width as f32 / 2.0)) / (10.0 * width as f32 / 64.);
             if d.abs() < PI / 2. {
                 255. * (d.cos())
             } else {
@@ -201,9 +345,38 @@ impl Field2D {
         Self::pixels_from_fn(width, height, f, |_, _| 0.)
     }
     fn standing(width: usize, height: usize) -> (Array2<f32>, Array2<f32>) {
-        let f = |x: usize, y: usize| 255. * (x as f32 / 64. * PI).cos();
+        // half a wavelength across the field
+        let f = |x: usize, _y: usize| 255. * (x as f32 / width as f32 * PI).cos();
         Self::pixels_from_fn(width, height, f, |_, _| 0.)
     }
+    /// A line source along the left edge sending a sustained plane wave of `wavelength` cells
+    /// towards +x, at the frequency the stencil gives that wavelength. It drives the second
+    /// column, since the vectorized path overwrites the edge cells with their neighbours.
+    fn plane_wave_source(&self, wavelength: f32) -> Source {
+        let c2 = self.c2[(1, self.height() / 2)];
+        let k = 2. * PI / wavelength;
+        Source {
+            shape: SourceShape::Line((1, 0), (1, self.height() - 1)),
+            waveform: Waveform::Sine,
+            frequency: super::wave_frequency(c2, k, self.stencil(), self.integrator, self.dt),
+            amplitude: SLIT_AMPLITUDE,
+            phase: 0.,
+            start: 0,
+            stop: None,
+        }
+    }
+    /// A wall two cells thick across the field at a third of its width, open at `slits`
+    /// (centers along y) with openings `slit_width` cells wide.
+    fn wall_with_slits(width: usize, height: usize, slits: &[usize], slit_width: usize) -> Array2<bool> {
+        let wall_x = width / 3;
+        Array2::from_shape_fn((width, height), |(x, y)| {
+            let in_wall = x == wall_x || x == wall_x + 1;
+            let in_slit = slits
+                .iter()
+                .any(|&center| y + slit_width / 2 >= center && y < center + slit_width - slit_width / 2);
+            in_wall && !in_slit
+        })
+    }
     fn pixels_from_fn(
         width: usize,
         height: usize,
@@ -220,30 +393,228 @@ impl Field2D {
         }
         (u, v)
     }
-    fn pixels_at_end() -> Vec<Vec<Pixel>> {
-        let mut pixels = vec![vec![Pixel::zero(); 128]; 128];
-        for j in 0..20 {
-            pixels[0][j] = Pixel {
-                u: (j as f32 * PI * 0.025).cos() * 255.0,
-                v: 0.0,
+    /// Impose the displacement of every active source on its cells.
+    fn apply_sources(&mut self) {
+        for source in &self.sources {
+            let Some(value) = source.value(self.step) else {
+                continue;
             };
+            for cell in source.cells() {
+                if let Some(u) = self.u.get_mut(cell) {
+                    self.v[cell] += (value - *u) / self.dt;
+                    *u = value;
+                }
+            }
         }
-        pixels
+    }
+
+    /// dv/dt for the state `(u, v)`: the wave equation's c^2 times the laplacian, minus damping.
+    /// The vectorized path rewrites the edges of `u` as ghost cells.
+    // ndarray's negative slice indices look like reversed ranges to clippy
+    #[allow(clippy::reversed_empty_ranges)]
+    fn acceleration(&self, u: &mut Array2<f32>, v: &Array2<f32>) -> Array2<f32> {
+        // `self.u` is moved out while integrating, so take the size from `u`
+        let (width, height) = u.dim();
+        let mut a = Array2::zeros((width, height));
+        if self.vectorized && self.boundary == Boundary::Periodic {
+            // every cell has all four neighbours on the torus, so update the whole array
+            let laplacian = -4. * &*u
+                + Self::rolled(u, Axis(0), 1)
+                + Self::rolled(u, Axis(0), -1)
+                + Self::rolled(u, Axis(1), 1)
+                + Self::rolled(u, Axis(1), -1);
+            a = &self.c2 * laplacian;
+        } else if self.vectorized {
+            // the following 4 updates ensure reflective boundaries,
+            // except on edges the Mur condition takes care of
+            if !self.absorbs(height) {
+                let r = 1. * &u.slice(s![1..-1, -2..-1]);
+                r.assign_to(u.slice_mut(s![1..-1, -1..]));
+
+                let r = 1. * &u.slice(s![1..-1, 1..2]);
+                r.assign_to(u.slice_mut(s![1..-1, ..1]));
+            }
+            if !self.absorbs(width) {
+                let r = 1. * &u.slice(s![-2..-1, 1..-1]);
+                r.assign_to(u.slice_mut(s![-1.., 1..-1]));
+
+                let r = 1. * &u.slice(s![1..2, 1..-1]);
+                r.assign_to(u.slice_mut(s![..1, 1..-1]));
+            }
+
+            let result = &self.c2.slice(s![1..-1, 1..-1])
+                * (-4. * &u.slice(s![1..-1, 1..-1])
+                    + u.slice(s![2.., 1..-1])
+                    + u.slice(s![..-2, 1..-1])
+                    + u.slice(s![1..-1, 2..])
+                    + u.slice(s![1..-1, ..-2]));
+
+            result.assign_to(a.slice_mut(s![1..-1, 1..-1]));
+        } else {
+            for x in 0..width {
+                for y in 0..height {
+                    a[(x, y)] += self.force(u, (x, y), (x as i32 + 1, y as i32));
+                    a[(x, y)] += self.force(u, (x, y), (x as i32 - 1, y as i32));
+                    a[(x, y)] += self.force(u, (x, y), (x as i32, y as i32 - 1));
+                    a[(x, y)] += self.force(u, (x, y), (x as i32, y as i32 + 1));
+
+                    a[(x, y)] += 0.0625 * self.force(u, (x, y), (x as i32, y as i32 + 2));
+                    a[(x, y)] += 0.0625 * self.force(u, (x, y), (x as i32, y as i32 - 2));
+                    a[(x, y)] += 0.0625 * self.force(u, (x, y), (x as i32 + 2, y as i32));
+                    a[(x, y)] += 0.0625 * self.force(u, (x, y), (x as i32 - 2, y as i32));
+                }
+            }
+        }
+
+        let sponge = self.boundary == Boundary::Sponge;
+        ndarray::Zip::from(&mut a)
+            .and(v)
+            .and(&self.loss)
+            .and(&self.sponge)
+            .for_each(|a, &v, &loss, &sponge_rate| {
+                let rate = self.damping + loss + if sponge { sponge_rate } else { 0. };
+                *a -= rate * v;
+            });
+        a
+    }
+
+    /// Sum of `c^2 |grad u|^2 / 2` over forward differences, wrapping around when periodic.
+    /// The loop path also couples cells two apart, which adds their differences at its weight.
+    /// The vectorized path's reflective edges are ghost cells outside the wave, so they're left out.
+    fn potential_energy(&self) -> f32 {
+        let (width, height) = (self.width(), self.height());
+        let periodic = self.boundary == Boundary::Periodic;
+        let ghost = |len| usize::from(self.vectorized && !periodic && !self.absorbs(len));
+        let (xs, ys) = (ghost(width)..width - ghost(width), ghost(height)..height - ghost(height));
+        let mut energy = 0.;
+        for x in xs.clone() {
+            for y in ys.clone() {
+                let mut grad2 = 0.;
+                for &(d, weight) in self.stencil() {
+                    if xs.contains(&(x + d)) || periodic {
+                        grad2 += weight * (self.u[((x + d) % width, y)] - self.u[(x, y)]).powi(2);
+                    }
+                    if ys.contains(&(y + d)) || periodic {
+                        grad2 += weight * (self.u[(x, (y + d) % height)] - self.u[(x, y)]).powi(2);
+                    }
+                }
+                energy += self.c2[(x, y)] * grad2 / 2.;
+            }
+        }
+        energy
+    }
+
+    /// Weight of the coupling to cells `d` apart along either axis for every `(d, weight)`. The
+    /// loop path also couples cells two apart, a sixteenth as strongly as neighbours.
+    fn stencil(&self) -> &'static [(usize, f32)] {
+        if self.vectorized {
+            &[(1, 1.)]
+        } else {
+            &[(1, 1.), (2, 0.0625)]
+        }
+    }
+
+    /// Pin the obstacle cells to zero.
+    fn apply_mask(&mut self) {
+        ndarray::Zip::from(&mut self.u)
+            .and(&mut self.v)
+            .and(&self.mask)
+            .for_each(|u, v, &masked| {
+                if masked {
+                    *u = 0.;
+                    *v = 0.;
+                }
+            });
+    }
+
+    /// Whether an axis of `len` cells gets absorbing (Mur) edges.
+    fn absorbs(&self, len: usize) -> bool {
+        self.boundary == Boundary::Absorbing && len >= MIN_ABSORBING_CELLS
+    }
+
+    fn c2_from_medium(medium: Medium, index: &Array2<f32>) -> Array2<f32> {
+        let (width, height) = index.dim();
+        Array2::from_shape_fn((width, height), |(x, y)| {
+            let index = index[(x, y)];
+            let x = (x as f32 + 0.5) / width as f32;
+            let y = (y as f32 + 0.5) / height as f32;
+            PROPAGATION_SPEED * medium.relative_c2(x, y) / (index * index)
+        })
+    }
+
+    fn loss_from_medium(width: usize, height: usize, medium: Medium) -> Array2<f32> {
+        Array2::from_shape_fn((width, height), |(x, y)| {
+            medium.damping((x as f32 + 0.5) / width as f32, (y as f32 + 0.5) / height as f32)
+        })
+    }
+
+    /// Speed of waves at `cell` in cells per unit time. The loop path's second-neighbour terms
+    /// (weight 0.0625 at distance 2) add a quarter to the effective c^2.
+    fn wave_speed(&self, cell: (usize, usize)) -> f32 {
+        if self.vectorized {
+            self.c2[cell].sqrt()
+        } else {
+            (1.25 * self.c2[cell]).sqrt()
+        }
+    }
+
+    /// Overwrite the edges with the first-order Mur condition
+    /// `u_edge' = u_inner + k (u_inner' - u_edge)`, which only lets waves travel outwards.
+    fn apply_mur(&mut self, u_prev: &Array2<f32>) {
+        let (width, height) = (self.width(), self.height());
+        if self.absorbs(width) {
+            for y in 0..height {
+                for (edge, inner) in [(0, 1), (width - 1, width - 2)] {
+                    let k = mur_coefficient(self.wave_speed((edge, y)) * self.dt);
+                    let u = u_prev[(inner, y)] + k * (self.u[(inner, y)] - u_prev[(edge, y)]);
+                    self.v[(edge, y)] = (u - u_prev[(edge, y)]) / self.dt;
+                    self.u[(edge, y)] = u;
+                }
+            }
+        }
+        if self.absorbs(height) {
+            for x in 0..width {
+                for (edge, inner) in [(0, 1), (height - 1, height - 2)] {
+                    let k = mur_coefficient(self.wave_speed((x, edge)) * self.dt);
+                    let u = u_prev[(x, inner)] + k * (self.u[(x, inner)] - u_prev[(x, edge)]);
+                    self.v[(x, edge)] = (u - u_prev[(x, edge)]) / self.dt;
+                    self.u[(x, edge)] = u;
+                }
+            }
+        }
+    }
+
+    /// Copy of `a` shifted by `shift` cells along `axis`, wrapping around at the edges.
+    fn rolled(a: &Array2<f32>, axis: Axis, shift: isize) -> Array2<f32> {
+        let len = a.len_of(axis) as isize;
+        let split = (len - shift).rem_euclid(len);
+        ndarray::concatenate(
+            axis,
+            &[
+                a.slice_axis(axis, ndarray::Slice::from(split..)),
+                a.slice_axis(axis, ndarray::Slice::from(..split)),
+            ],
+        )
+        .unwrap()
     }
 
     // attempt to simulate the second derivative
-    fn force(&self, target: (usize, usize), source: (i32, i32)) -> f32 {
-        if source.0 < 0 || source.0 >= self.width() as i32 {
+    fn force(&self, u: &Array2<f32>, target: (usize, usize), source: (i32, i32)) -> f32 {
+        let (width, height) = u.dim();
+        let source = if self.boundary == Boundary::Periodic {
+            (source.0.rem_euclid(width as i32), source.1.rem_euclid(height as i32))
+        } else {
+            source
+        };
+        if source.0 < 0 || source.0 >= width as i32 {
             return 0f32;
         }
-        if source.1 < 0 || source.1 >= self.height() as i32 {
+        if source.1 < 0 || source.1 >= height as i32 {
             return 0f32;
         }
         let source = (source.0 as usize, source.1 as usize);
         // c^2
-        PROPAGATION_SPEED
-            * (self.u.get((source.0, source.1)).unwrap()
-                - self.u.get((target.0, target.1)).unwrap())
+        self.c2[target] * (u.get((source.0, source.1)).unwrap() - u.get((target.0, target.1)).unwrap())
     }
     fn width(&self) -> usize {
         self.u.shape()[0]
@@ -252,3 +623,112 @@ impl Field2D {
         self.u.shape()[1]
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(field: &mut Field2D, steps: usize) {
+        for _ in 0..steps {
+            field.update();
+        }
+    }
+
+    fn max_abs(field: &Field2D) -> f32 {
+        field.u.iter().fold(0f32, |max, x| max.max(x.abs()))
+    }
+
+    #[test]
+    fn both_paths_stay_bounded() {
+        for vectorized in [true, false] {
+            let mut field = Field2D::new(vectorized, Field2DInit::Centered, (32, 32), FieldConfig::default());
+            let start = max_abs(&field);
+            run(&mut field, 600);
+            assert!(!field.diverged(), "vectorized: {vectorized}");
+            assert!(max_abs(&field) <= start, "vectorized: {vectorized}, {} > {start}", max_abs(&field));
+        }
+    }
+
+    #[test]
+    fn reflective_energy_drift_stays_small() {
+        for vectorized in [true, false] {
+            let mut field = Field2D::new(vectorized, Field2DInit::Centered, (32, 32), FieldConfig::default());
+            for _ in 0..6 {
+                run(&mut field, 100);
+                let drift = field.diagnostics().drift;
+                assert!(drift.abs() < 0.02, "vectorized: {vectorized}, drift {drift} at step {}", field.step);
+            }
+        }
+    }
+
+    #[test]
+    fn open_boundaries_let_the_pulse_out() {
+        for boundary in [Boundary::Absorbing, Boundary::Sponge] {
+            for vectorized in [true, false] {
+                let config = FieldConfig { boundary, ..FieldConfig::default() };
+                let mut field = Field2D::new(vectorized, Field2DInit::Centered, (48, 48), config);
+                let start = field.diagnostics().total();
+                run(&mut field, 600);
+                let left = field.diagnostics().total() / start;
+                assert!(left < 0.1, "{boundary:?}, vectorized: {vectorized}, {left} of the energy left");
+            }
+        }
+    }
+
+    #[test]
+    fn periodic_energy_drift_stays_small() {
+        for vectorized in [true, false] {
+            let config = FieldConfig { boundary: Boundary::Periodic, ..FieldConfig::default() };
+            let mut field = Field2D::new(vectorized, Field2DInit::Centered, (32, 32), config);
+            for _ in 0..6 {
+                run(&mut field, 100);
+                let drift = field.diagnostics().drift;
+                assert!(drift.abs() < 0.02, "vectorized: {vectorized}, drift {drift} at step {}", field.step);
+            }
+        }
+    }
+
+    #[test]
+    fn periodic_pulse_wraps_around() {
+        // largest |u| along the far right strip once the left-going half of the pulse has had
+        // time to cross the left edge, but the right-going half is still far from the right one
+        let far_edge = |boundary: Boundary, vectorized: bool| {
+            let config = FieldConfig { boundary, ..FieldConfig::default() };
+            let mut field = Field2D::new(vectorized, Field2DInit::Zero, (64, 16), config);
+            field.add_disturbance((6., 8.), Disturbance { radius: 4., amplitude: 100. });
+            run(&mut field, 150);
+            field.u.slice(s![52.., ..]).iter().fold(0f32, |max, x| max.max(x.abs()))
+        };
+        for vectorized in [true, false] {
+            let periodic = far_edge(Boundary::Periodic, vectorized);
+            let reflective = far_edge(Boundary::Reflective, vectorized);
+            assert!(periodic > 5., "vectorized: {vectorized}, {periodic} reached the far edge");
+            assert!(reflective < 0.5, "vectorized: {vectorized}, {reflective} reached the far edge without wrapping");
+        }
+    }
+
+    #[test]
+    fn slit_source_follows_the_settings() {
+        let mut field = Field2D::new(false, Field2DInit::DoubleSlit, (64, 64), FieldConfig::default());
+        let before = field.sources[0].frequency;
+        let config = FieldConfig { medium: Medium::GlassSlab, integrator: Integrator::Verlet, dt: 0.5, ..FieldConfig::default() };
+        field.configure(config);
+        let wavelength = field.slit_wavelength.unwrap();
+        assert_eq!(field.sources, [field.plane_wave_source(wavelength)]);
+        assert_ne!(field.sources[0].frequency, before);
+    }
+
+    #[test]
+    fn masked_cells_stay_at_rest() {
+        for vectorized in [true, false] {
+            let mut field = Field2D::new(vectorized, Field2DInit::DoubleSlit, (32, 32), FieldConfig::default());
+            run(&mut field, 200);
+            // the wave has to have reached the wall for its cells to be tested
+            let before_wall = field.u.row(field.width() / 3 - 1).iter().fold(0f32, |max, x| max.max(x.abs()));
+            assert!(before_wall > 1., "vectorized: {vectorized}, the wave hasn't reached the wall");
+            for ((cell, &masked), (&u, &v)) in field.mask.indexed_iter().zip(field.u.iter().zip(&field.v)) {
+                assert!(!masked || (u == 0. && v == 0.), "vectorized: {vectorized}, cell {cell:?}: ({u}, {v})");
+            }
+        }
+    }
+}
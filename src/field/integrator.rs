@@ -0,0 +1,176 @@
+use ndarray::{Array, Dimension};
+
+/// Scheme used to advance `u` and `v` by one time step.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum Integrator {
+    /// `v += a dt`, then `u += v dt`
+    SymplecticEuler,
+    /// Störmer-Verlet in kick-drift-kick form: half a velocity step on either side of the
+    /// displacement step
+    Verlet,
+    /// classic fourth order Runge-Kutta on the pair `(u, v)`
+    Rk4,
+}
+
+impl Integrator {
+    pub fn cycle(self) -> Self {
+        match self {
+            Self::SymplecticEuler => Self::Verlet,
+            Self::Verlet => Self::Rk4,
+            Self::Rk4 => Self::SymplecticEuler,
+        }
+    }
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::SymplecticEuler => "euler",
+            Self::Verlet => "verlet",
+            Self::Rk4 => "rk4",
+        }
+    }
+
+    /// Largest `ω dt` the scheme stays stable at for an undamped oscillator of angular
+    /// frequency `ω`.
+    pub fn stability_limit(self) -> f32 {
+        match self {
+            Self::SymplecticEuler | Self::Verlet => 2.,
+            Self::Rk4 => 2. * std::f32::consts::SQRT_2,
+        }
+    }
+
+    /// Largest stable CFL number `c dt / dx` on a grid with `dims` dimensions. The fastest mode
+    /// of the discrete laplacian oscillates at `ω = 2 c sqrt(dims) / dx`.
+    pub fn cfl_limit(self, dims: usize) -> f32 {
+        self.stability_limit() / (2. * (dims as f32).sqrt())
+    }
+
+    /// Phase an undamped oscillator of angular frequency `ω` advances by in one step, given
+    /// `ω dt`. The symplectic schemes oscillate slightly faster than `ω`, Runge-Kutta slightly
+    /// slower; NaN past the symplectic schemes' stability limit.
+    pub fn phase_per_step(self, omega_dt: f32) -> f32 {
+        match self {
+            Self::SymplecticEuler | Self::Verlet => 2. * (omega_dt / 2.).asin(),
+            Self::Rk4 => {
+                // argument of the amplification factor 1 + z + z^2/2 + z^3/6 + z^4/24, z = i ω dt
+                let x = omega_dt;
+                (x - x.powi(3) / 6.).atan2(1. - x * x / 2. + x.powi(4) / 24.)
+            }
+        }
+    }
+
+    /// Advance `u` and `v` by `dt`, where `acceleration(u, v)` gives `dv/dt`. It may rewrite
+    /// `u` in place, e.g. to fill in ghost cells at the edges.
+    pub fn step<D: Dimension>(
+        self,
+        u: &mut Array<f32, D>,
+        v: &mut Array<f32, D>,
+        dt: f32,
+        mut acceleration: impl FnMut(&mut Array<f32, D>, &Array<f32, D>) -> Array<f32, D>,
+    ) {
+        match self {
+            Self::SymplecticEuler => {
+                let a = acceleration(u, v);
+                v.scaled_add(dt, &a);
+                u.scaled_add(dt, v);
+            }
+            Self::Verlet => {
+                let a = acceleration(u, v);
+                v.scaled_add(dt / 2., &a);
+                u.scaled_add(dt, v);
+                let a = acceleration(u, v);
+                v.scaled_add(dt / 2., &a);
+            }
+            Self::Rk4 => {
+                let k1_u = v.clone();
+                let k1_v = acceleration(u, v);
+
+                let mut u2 = &*u + &(dt / 2. * &k1_u);
+                let k2_u = &*v + &(dt / 2. * &k1_v);
+                let k2_v = acceleration(&mut u2, &k2_u);
+
+                let mut u3 = &*u + &(dt / 2. * &k2_u);
+                let k3_u = &*v + &(dt / 2. * &k2_v);
+                let k3_v = acceleration(&mut u3, &k3_u);
+
+                let mut u4 = &*u + &(dt * &k3_u);
+                let k4_u = &*v + &(dt * &k3_v);
+                let k4_v = acceleration(&mut u4, &k4_u);
+
+                u.scaled_add(dt / 6., &(k1_u + 2. * k2_u + 2. * k3_u + k4_u));
+                v.scaled_add(dt / 6., &(k1_v + 2. * k2_v + 2. * k3_v + k4_v));
+            }
+        }
+    }
+
+    /// Undo a `step` of `dt`. The symplectic schemes retrace their own steps in reverse order,
+    /// which is exact in exact arithmetic as long as `acceleration` doesn't depend on `v`, i.e.
+    /// without damping. Runge-Kutta isn't reversible and just steps by `-dt`.
+    pub fn step_back<D: Dimension>(
+        self,
+        u: &mut Array<f32, D>,
+        v: &mut Array<f32, D>,
+        dt: f32,
+        mut acceleration: impl FnMut(&mut Array<f32, D>, &Array<f32, D>) -> Array<f32, D>,
+    ) {
+        match self {
+            Self::SymplecticEuler => {
+                u.scaled_add(-dt, v);
+                let a = acceleration(u, v);
+                v.scaled_add(-dt, &a);
+            }
+            Self::Verlet => {
+                let a = acceleration(u, v);
+                v.scaled_add(-dt / 2., &a);
+                u.scaled_add(-dt, v);
+                let a = acceleration(u, v);
+                v.scaled_add(-dt / 2., &a);
+            }
+            Self::Rk4 => self.step(u, v, -dt, acceleration),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ndarray::Array1;
+
+    /// A string of unit masses coupled by unit springs, held at both ends.
+    fn chain(u: &mut Array1<f32>, _v: &Array1<f32>) -> Array1<f32> {
+        let len = u.len();
+        Array1::from_shape_fn(len, |i| {
+            let left = if i > 0 { u[i - 1] } else { 0. };
+            let right = if i + 1 < len { u[i + 1] } else { 0. };
+            left + right - 2. * u[i]
+        })
+    }
+
+    /// Largest difference after `steps` steps of `dt` forward and as many back.
+    fn round_trip_error(integrator: Integrator, steps: usize, dt: f32) -> f32 {
+        let u0 = Array1::from_shape_fn(32, |i| (i as f32 * 0.3).sin());
+        let v0 = Array1::from_shape_fn(32, |i| (i as f32 * 0.7).cos());
+        let (mut u, mut v) = (u0.clone(), v0.clone());
+        for _ in 0..steps {
+            integrator.step(&mut u, &mut v, dt, chain);
+        }
+        for _ in 0..steps {
+            integrator.step_back(&mut u, &mut v, dt, chain);
+        }
+        let diff = |a: &Array1<f32>, b: &Array1<f32>| a.iter().zip(b).fold(0f32, |max, (a, b)| max.max((a - b).abs()));
+        diff(&u, &u0).max(diff(&v, &v0))
+    }
+
+    #[test]
+    fn symplectic_schemes_retrace_their_steps() {
+        for integrator in [Integrator::SymplecticEuler, Integrator::Verlet] {
+            let error = round_trip_error(integrator, 200, 0.5);
+            assert!(error < 1e-4, "{}: off by {error}", integrator.label());
+        }
+    }
+
+    #[test]
+    fn rk4_comes_close_to_where_it_started_at_small_steps() {
+        // its amplitude decays a little both ways, so it only gets close
+        let error = round_trip_error(Integrator::Rk4, 200, 0.1);
+        assert!(error < 1e-2, "off by {error}");
+    }
+}
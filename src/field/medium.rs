@@ -0,0 +1,79 @@
+/// Layout of the wave speed and losses across the field. Each preset gives the local c^2
+/// relative to the field's base propagation speed, so slower regions have a higher refractive
+/// index, and the local damping on top of the field's global damping.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum Medium {
+    /// same speed everywhere
+    Uniform,
+    /// a band across the middle third with refractive index 1.5
+    GlassSlab,
+    /// a band whose index peaks at 1.5 on the center line and falls to 1 at its edges,
+    /// focusing waves that pass through it
+    GradientLens,
+    /// the far half is shallow water, where waves travel at half the speed
+    ShallowStep,
+    /// a band across the middle third that absorbs waves passing through it
+    LossySlab,
+}
+
+/// damping inside `Medium::LossySlab`
+const LOSSY_DAMPING: f32 = 0.02;
+
+impl Medium {
+    pub fn cycle(self) -> Self {
+        match self {
+            Self::Uniform => Self::GlassSlab,
+            Self::GlassSlab => Self::GradientLens,
+            Self::GradientLens => Self::ShallowStep,
+            Self::ShallowStep => Self::LossySlab,
+            Self::LossySlab => Self::Uniform,
+        }
+    }
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Uniform => "uniform",
+            Self::GlassSlab => "glass slab",
+            Self::GradientLens => "gradient lens",
+            Self::ShallowStep => "shallow step",
+            Self::LossySlab => "lossy slab",
+        }
+    }
+
+    /// c^2 relative to the base speed at `(x, y)`, both normalized to `0..1` across the field.
+    pub fn relative_c2(self, x: f32, y: f32) -> f32 {
+        let index = match self {
+            Self::Uniform | Self::LossySlab => 1.,
+            Self::GlassSlab => {
+                if (1. / 3. ..2. / 3.).contains(&x) {
+                    1.5
+                } else {
+                    1.
+                }
+            }
+            Self::GradientLens => {
+                if (0.4..0.6).contains(&x) {
+                    let dy = (y - 0.5) * 2.;
+                    1. + 0.5 * (1. - dy * dy)
+                } else {
+                    1.
+                }
+            }
+            Self::ShallowStep => {
+                if x >= 0.5 {
+                    2.
+                } else {
+                    1.
+                }
+            }
+        };
+        1. / (index * index)
+    }
+
+    /// Fraction of the velocity lost per unit time at `(x, y)`, both normalized to `0..1`.
+    pub fn damping(self, x: f32, _y: f32) -> f32 {
+        match self {
+            Self::LossySlab if (1. / 3. ..2. / 3.).contains(&x) => LOSSY_DAMPING,
+            _ => 0.,
+        }
+    }
+}
@@ -1,23 +1,186 @@
+mod boundary;
 mod field1d;
 mod field2d;
+mod integrator;
+mod medium;
+mod source;
 
+pub use boundary::Boundary;
 pub use field1d::{Field1D, Field1DInit};
 pub use field2d::{Field2D, Field2DInit};
+pub use integrator::Integrator;
+pub use medium::Medium;
+pub use source::{Source, SourceKind, SourceShape, Waveform, DEFAULT_FREQUENCY, PULSE_DURATION};
 
-use macroquad::prelude::*;
+use std::f32::consts::PI;
 
+use ndarray::{Array, ArrayD, ArrayViewD, Dimension, ErrorKind, ShapeError};
+
+/// displacement beyond which a field counts as diverged
+const DIVERGENCE_THRESHOLD: f32 = 1e6;
+
+/// Physical and numerical settings shared by both kinds of field, adjustable at runtime.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct FieldConfig {
+    pub boundary: Boundary,
+    pub medium: Medium,
+    /// fraction of the velocity lost per unit time
+    pub damping: f32,
+    pub integrator: Integrator,
+    /// time step; the cell size is 1
+    pub dt: f32,
+}
+
+impl Default for FieldConfig {
+    fn default() -> Self {
+        FieldConfig {
+            boundary: Boundary::Reflective,
+            medium: Medium::Uniform,
+            damping: 0.,
+            integrator: Integrator::SymplecticEuler,
+            dt: 1.,
+        }
+    }
+}
+
+/// A cosine-shaped bump injected into the field, e.g. by clicking on it.
 #[derive(Copy, Clone, Debug)]
-pub struct Pixel {
-    u: f32,
-    v: f32,
+pub struct Disturbance {
+    /// radius of the bump, in cells
+    pub radius: f32,
+    /// displacement added at the center of the bump
+    pub amplitude: f32,
+}
+
+impl Disturbance {
+    /// Displacement added at `distance` cells from the center of the bump.
+    pub fn displacement(&self, distance: f32) -> f32 {
+        if distance < self.radius {
+            self.amplitude * (distance / self.radius * PI / 2.).cos()
+        } else {
+            0.
+        }
+    }
+}
+
+impl Default for Disturbance {
+    fn default() -> Self {
+        Disturbance {
+            radius: 8.,
+            amplitude: 255.,
+        }
+    }
+}
+
+/// Conserved quantities and norms of a field's state.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct Diagnostics {
+    /// sum of v^2 / 2
+    pub kinetic: f32,
+    /// sum of c^2 |grad u|^2 / 2
+    pub potential: f32,
+    /// total energy relative to its value at step 0, minus one
+    pub drift: f32,
+    /// L2 norm of u
+    pub l2: f32,
+    /// largest |u|
+    pub max: f32,
 }
-impl Pixel {
-    fn zero() -> Self {
-        Pixel { u: 0.0, v: 0.0 }
+
+impl Diagnostics {
+    pub fn total(&self) -> f32 {
+        self.kinetic + self.potential
     }
 }
 
+/// A simulated wave field. Drawing is left to the renderers in the app, so fields run
+/// without a window.
 pub trait Field {
-    fn render(&mut self);
     fn update(&mut self);
+    /// Undo one `update`, running time backwards. This retraces the integrator's steps, which
+    /// is exact up to rounding without damping, absorbing edges or sources; those lose
+    /// information going forwards and aren't undone.
+    fn update_backwards(&mut self);
+    /// Apply new settings, keeping the current state.
+    fn configure(&mut self, config: FieldConfig);
+    /// CFL number `c dt / dx` for the fastest cell.
+    fn cfl(&self) -> f32;
+    /// Largest CFL number the current integrator is stable at.
+    fn cfl_limit(&self) -> f32;
+    /// Frequency in cycles per step of the slowest standing wave along x, half a wavelength
+    /// between the reflective edges, as the `Standing` preset sets up. It follows from the
+    /// discrete dispersion relation of the stencil and the integrator, so it's what a probe
+    /// should measure. `None` unless the edges reflect and the medium is uniform and lossless.
+    fn fundamental_frequency(&self) -> Option<f32>;
+    fn diagnostics(&self) -> Diagnostics;
+    /// Whether the state has picked up NaNs or infinities or grown out of bounds.
+    fn diverged(&self) -> bool;
+    /// Number of updates since the field was created.
+    fn step(&self) -> u32;
+    /// Carry on counting from `step`, e.g. when resuming a saved state.
+    fn set_step(&mut self, step: u32);
+    fn add_source(&mut self, source: Source);
+    fn sources(&self) -> &[Source];
+    fn clear_sources(&mut self);
+    /// displacement of every cell
+    fn u(&self) -> ArrayViewD<'_, f32>;
+    /// velocity of every cell
+    fn v(&self) -> ArrayViewD<'_, f32>;
+    /// local c^2 of every cell
+    fn c2(&self) -> ArrayViewD<'_, f32>;
+    /// refractive index of every cell relative to the medium preset
+    fn index(&self) -> ArrayViewD<'_, f32>;
+    /// obstacle cells, `None` for fields without obstacles
+    fn mask(&self) -> Option<ArrayViewD<'_, bool>>;
+    /// Replace the state with `u` and `v` of the field's shape, e.g. a saved one, making it the
+    /// reference for the energy drift.
+    fn set_state(&mut self, u: ArrayD<f32>, v: ArrayD<f32>) -> Result<(), ShapeError>;
+    /// Go back to the state `u` and `v` this field had at `step`, keeping the energy reference
+    /// so the drift is still measured from the start of the run.
+    fn restore_state(&mut self, u: ArrayD<f32>, v: ArrayD<f32>, step: u32) -> Result<(), ShapeError>;
+    /// Add a disturbance centered on `cell`, given in (fractional) grid coordinates.
+    fn add_disturbance(&mut self, cell: (f32, f32), disturbance: Disturbance);
+}
+
+fn diverged<D: Dimension>(u: &Array<f32, D>, v: &Array<f32, D>) -> bool {
+    u.iter()
+        .chain(v.iter())
+        .any(|x| !x.is_finite() || x.abs() > DIVERGENCE_THRESHOLD)
+}
+
+/// displacement and velocity of every cell
+type State<D> = (Array<f32, D>, Array<f32, D>);
+
+/// Check `u` and `v` against a field's `shape` and convert them to its dimensionality.
+fn fit_state<D: Dimension>(u: ArrayD<f32>, v: ArrayD<f32>, shape: &[usize]) -> Result<State<D>, ShapeError> {
+    if u.shape() != shape || v.shape() != shape {
+        return Err(ShapeError::from_kind(ErrorKind::IncompatibleShape));
+    }
+    Ok((u.into_dimensionality()?, v.into_dimensionality()?))
+}
+
+/// Kinetic energy and norms of `u`, i.e. everything but the potential energy and drift.
+fn state_diagnostics<D: Dimension>(u: &Array<f32, D>, v: &Array<f32, D>) -> Diagnostics {
+    Diagnostics {
+        kinetic: v.iter().map(|v| v * v / 2.).sum(),
+        l2: u.iter().map(|u| u * u).sum::<f32>().sqrt(),
+        max: u.iter().fold(0., |max, u| max.max(u.abs())),
+        ..Default::default()
+    }
+}
+
+/// Frequency in cycles per step of a wave of wavenumber `k` along one axis, on a stencil
+/// coupling cells `d` apart with weight `w` for every `(d, w)` in `stencil`.
+fn wave_frequency(c2: f32, k: f32, stencil: &[(usize, f32)], integrator: Integrator, dt: f32) -> f32 {
+    let omega2: f32 = stencil.iter().map(|&(d, w)| w * c2 * 4. * (d as f32 * k / 2.).sin().powi(2)).sum();
+    integrator.phase_per_step(omega2.sqrt() * dt) / (2. * PI)
+}
+
+/// Relative change from `initial` to `total`, or 0 when there was no energy to begin with.
+fn drift(total: f32, initial: f32) -> f32 {
+    if initial > 0. {
+        total / initial - 1.
+    } else {
+        0.
+    }
 }
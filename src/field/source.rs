@@ -0,0 +1,148 @@
+use std::f32::consts::PI;
+
+/// default frequency of placed sources, in cycles per step
+pub const DEFAULT_FREQUENCY: f32 = 0.0125;
+/// default number of steps a pulse source stays on
+pub const PULSE_DURATION: u32 = 120;
+
+/// Where a source drives the field, in grid cells.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum SourceShape {
+    Point((usize, usize)),
+    Line((usize, usize), (usize, usize)),
+}
+
+/// How a source's displacement varies over time.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum Waveform {
+    Sine,
+    /// a sine under a Gaussian envelope filling the source's start..stop window
+    GaussianPulse,
+}
+
+/// A hard source: while active, it sets the displacement of its cells every step.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct Source {
+    pub shape: SourceShape,
+    pub waveform: Waveform,
+    /// cycles per step
+    pub frequency: f32,
+    pub amplitude: f32,
+    /// radians
+    pub phase: f32,
+    /// first step the source is active at
+    pub start: u32,
+    /// step the source switches off at, `None` to keep it running
+    pub stop: Option<u32>,
+}
+
+impl Source {
+    /// Displacement the source imposes at `step`, or `None` while it's inactive.
+    pub fn value(&self, step: u32) -> Option<f32> {
+        if step < self.start || self.stop.is_some_and(|stop| step >= stop) {
+            return None;
+        }
+        let t = (step - self.start) as f32;
+        match self.waveform {
+            Waveform::Sine => Some(self.amplitude * (2. * PI * self.frequency * t + self.phase).sin()),
+            Waveform::GaussianPulse => {
+                // centered in the window, which spans six standard deviations
+                let duration = self.stop.map_or(PULSE_DURATION, |stop| stop - self.start) as f32;
+                let t = t - duration / 2.;
+                let envelope = (-0.5 * (t / (duration / 6.)).powi(2)).exp();
+                Some(envelope * self.amplitude * (2. * PI * self.frequency * t + self.phase).cos())
+            }
+        }
+    }
+
+    /// Whether the source lies on a grid of `shape`, `[width]` or `[width, height]`, where 1D
+    /// grids ignore y. Only the endpoints are checked, so it's cheap for lines however long.
+    pub fn fits(&self, shape: &[usize]) -> bool {
+        let on_grid = |(x, y): (usize, usize)| x < shape[0] && shape.get(1).is_none_or(|&height| y < height);
+        match self.shape {
+            SourceShape::Point(at) => on_grid(at),
+            SourceShape::Line(from, to) => on_grid(from) && on_grid(to),
+        }
+    }
+
+    /// Cells the source drives. Lines are rasterized with one cell per step along their longer axis.
+    pub fn cells(&self) -> Vec<(usize, usize)> {
+        match self.shape {
+            SourceShape::Point(cell) => vec![cell],
+            SourceShape::Line(from, to) => {
+                let dx = to.0 as f32 - from.0 as f32;
+                let dy = to.1 as f32 - from.1 as f32;
+                let steps = dx.abs().max(dy.abs()) as usize;
+                (0..=steps)
+                    .map(|i| {
+                        let t = if steps == 0 { 0. } else { i as f32 / steps as f32 };
+                        (
+                            (from.0 as f32 + t * dx).round() as usize,
+                            (from.1 as f32 + t * dy).round() as usize,
+                        )
+                    })
+                    .collect()
+            }
+        }
+    }
+
+    pub fn label(&self) -> String {
+        let waveform = match self.waveform {
+            Waveform::Sine => "sine",
+            Waveform::GaussianPulse => "pulse",
+        };
+        let shape = match self.shape {
+            SourceShape::Point((x, y)) => format!("({x},{y})"),
+            SourceShape::Line((x0, y0), (x1, y1)) => format!("({x0},{y0})-({x1},{y1})"),
+        };
+        format!("{waveform} {shape} f={:.4} a={:.0}", self.frequency, self.amplitude)
+    }
+}
+
+/// Kind of source placed with the mouse.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum SourceKind {
+    Point,
+    Line,
+    Pulse,
+}
+
+impl SourceKind {
+    pub fn cycle(self) -> Self {
+        match self {
+            Self::Point => Self::Line,
+            Self::Line => Self::Pulse,
+            Self::Pulse => Self::Point,
+        }
+    }
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Point => "point",
+            Self::Line => "line",
+            Self::Pulse => "pulse",
+        }
+    }
+
+    /// A source of this kind spanning `from`..`to` (only lines use `to`), switched on at `start`.
+    pub fn place(self, from: (usize, usize), to: (usize, usize), amplitude: f32, start: u32) -> Source {
+        let (shape, waveform, frequency, stop) = match self {
+            Self::Point => (SourceShape::Point(from), Waveform::Sine, DEFAULT_FREQUENCY, None),
+            Self::Line => (SourceShape::Line(from, to), Waveform::Sine, DEFAULT_FREQUENCY, None),
+            Self::Pulse => (
+                SourceShape::Point(from),
+                Waveform::GaussianPulse,
+                0.,
+                Some(start + PULSE_DURATION),
+            ),
+        };
+        Source {
+            shape,
+            waveform,
+            frequency,
+            amplitude,
+            phase: 0.,
+            start,
+            stop,
+        }
+    }
+}
@@ -1,12 +1,41 @@
+use std::path::{Path, PathBuf};
 use std::time::{Duration, Instant};
 
 use macroquad::prelude::*;
 use miniquad::window::quit;
+use ndarray::ArrayD;
 
-use crate::field::{Field, Field1D, Field1DInit, Field2D, Field2DInit};
+use crate::animation::{GifOptions, GifWriter};
+use crate::colormap::{ColorRange, ColorScale};
+use crate::field::{Disturbance, Field, Field1D, Field1DInit, Field2D, Field2DInit, FieldConfig, SourceKind};
+use crate::picker::{Pick, ScenarioPicker};
+use crate::probe::Probes;
+use crate::render::{probe_color, Field1DRenderer, Field2DRenderer, FieldRenderer, Recorder};
+use crate::rewind::Rewind;
+use crate::scenario::Scenario;
+use crate::snapshot::Snapshot;
 
 const MIN_UPDATES_PER_FRAME: u32 = 1;
 const MAX_UPDATES_PER_FRAME: u32 = 256;
+/// smallest non-zero damping, stepping down from it turns damping off
+const MIN_DAMPING: f32 = 0.0005;
+const MAX_DAMPING: f32 = 0.128;
+const MIN_DT: f32 = 1. / 16.;
+const MAX_DT: f32 = 16.;
+const MIN_DISTURBANCE_RADIUS: f32 = 1.;
+const MAX_DISTURBANCE_RADIUS: f32 = 64.;
+const MIN_DISTURBANCE_AMPLITUDE: f32 = 16.;
+const MAX_DISTURBANCE_AMPLITUDE: f32 = 4096.;
+/// zoom per notch of the mouse wheel
+const ZOOM_STEP: f32 = 1.25;
+const MIN_GRID_SIZE: usize = 3;
+const MAX_GRID_SIZE: usize = 1024;
+/// directory snapshots are saved to and loaded from
+const SNAPSHOT_DIR: &str = "snapshots";
+/// directory GIFs recorded with G are written to
+const GIF_DIR: &str = "recordings";
+/// directory probe recordings are exported to with E
+const PROBE_DIR: &str = "probes";
 
 #[derive(PartialEq, Eq)]
 pub enum GameState {
@@ -39,53 +68,191 @@ impl InitVariant {
             Self::Field2D(i) => i.label(),
         }
     }
+    pub fn default_size(self) -> (usize, usize) {
+        match self {
+            Self::Field1D(i) => (i.default_width(), 1),
+            Self::Field2D(i) => i.default_size(),
+        }
+    }
+}
+
+/// State the field started from, to see how close running time backwards gets back to it.
+struct Origin {
+    /// step of the field at the start
+    step: u32,
+    u: ArrayD<f32>,
+    v: ArrayD<f32>,
 }
 
 pub struct Game {
     pub field: Box<dyn Field>,
+    /// draws `field`, picked to match `field_type`
+    renderer: Box<dyn FieldRenderer>,
     pub field_type: FieldType,
     pub state: GameState,
-    pub step: i32,
     pub just_updated: bool,
     pub start_time: Instant,
     pub rendering_duration: Duration,
     pub update_duration: Duration,
     pub init_variant: InitVariant,
+    pub config: FieldConfig,
+    /// grid width asked for on the command line or in the app, `None` to use the init's own
+    pub width: Option<usize>,
+    /// grid height, ignored by 1D fields
+    pub height: Option<usize>,
+    /// step at which the field blew up, if it did
+    pub diverged_at: Option<u32>,
     pub updates_per_frame: u32,
+    pub disturbance: Disturbance,
+    /// cell where the last disturbance of the current mouse drag was placed
+    last_disturbance: Option<Vec2>,
+    /// kind of source placed with the right mouse button
+    pub source_kind: SourceKind,
+    /// cell where the right mouse button went down, the start of a line source
+    source_anchor: Option<(usize, usize)>,
+    /// scenario the field is set up from instead of `init_variant`
+    pub scenario: Option<Scenario>,
+    /// list of scenario files to load one from, open after pressing O
+    picker: Option<ScenarioPicker>,
+    /// whether the left mouse button is still down from the click that closed the picker, which
+    /// mustn't go on to disturb the field
+    swallow_click: bool,
+    /// last thing that went wrong, e.g. a scenario that failed to load
+    pub error: Option<String>,
+    /// outcome of the last file operation that went right, e.g. where a snapshot was saved
+    pub notice: Option<String>,
+    /// whether the field runs on the vectorized path
+    pub vectorized: bool,
+    /// PNG recording in progress, toggled with P
+    pub recorder: Option<Recorder>,
+    /// record one in this many rendered frames
+    pub record_every: u32,
+    /// upscaling factor of recorded frames
+    pub record_scale: u32,
+    /// GIF recording in progress, toggled with G
+    pub gif: Option<GifWriter>,
+    /// frame interval, delay, palette and length of GIFs recorded with G
+    pub gif_options: GifOptions,
+    /// recent states to step back through with `,` or scrub through on the timeline
+    pub rewind: Rewind,
+    /// whether the left mouse button went down on the timeline and is dragging it
+    scrubbing: bool,
+    /// cells sampled every step, placed with ctrl and the left mouse button
+    pub probes: Probes,
+    /// cell where a shift-drag with the left mouse button started, the start of a new
+    /// cross-section
+    section_anchor: Option<Vec2>,
+    /// mouse position at the last frame of a middle button drag, which pans the view
+    pan_from: Option<Vec2>,
+    /// colormap and range the field is drawn with
+    pub colors: ColorScale,
+    /// fit the color range to the field every frame, toggled with A
+    pub auto_range: bool,
+    /// whether time runs backwards, toggled with Z
+    pub reversed: bool,
+    /// state at the start of the run, `None` until the first forward update after a reset
+    origin: Option<Origin>,
 }
 
 impl Game {
-    pub fn new(field_type: FieldType) -> Game {
+    pub fn new(field_type: FieldType, width: Option<usize>, height: Option<usize>) -> Game {
         let init_variant = match field_type {
             FieldType::Field1D => InitVariant::Field1D(Field1DInit::AtEnd),
             FieldType::Field2D => InitVariant::Field2D(Field2DInit::Standing),
         };
         Game {
-            field: Self::init_field(field_type, false, init_variant),
+            field: Self::init_field(field_type, false, init_variant, (width, height), FieldConfig::default()),
+            renderer: Self::init_renderer(field_type),
             field_type,
             state: GameState::Paused,
-            step: 0,
             just_updated: false,
             start_time: Instant::now(),
             rendering_duration: Duration::ZERO,
             update_duration: Duration::ZERO,
             init_variant,
+            config: FieldConfig::default(),
+            width,
+            height,
+            diverged_at: None,
             updates_per_frame: 4,
+            disturbance: Disturbance::default(),
+            last_disturbance: None,
+            source_kind: SourceKind::Point,
+            source_anchor: None,
+            scenario: None,
+            picker: None,
+            swallow_click: false,
+            error: None,
+            notice: None,
+            vectorized: false,
+            recorder: None,
+            record_every: 1,
+            record_scale: 1,
+            gif: None,
+            gif_options: GifOptions::default(),
+            rewind: Rewind::new(),
+            scrubbing: false,
+            probes: Probes::new(),
+            section_anchor: None,
+            pan_from: None,
+            colors: ColorScale::default(),
+            auto_range: false,
+            reversed: false,
+            origin: None,
         }
     }
 
-    pub fn init_field(field_type: FieldType, vectorized: bool, init_variant: InitVariant) -> Box<dyn Field> {
+    /// Build a field of `size` (width, height), where a missing side falls back to the init's own.
+    pub fn init_field(
+        field_type: FieldType,
+        vectorized: bool,
+        init_variant: InitVariant,
+        size: (Option<usize>, Option<usize>),
+        config: FieldConfig,
+    ) -> Box<dyn Field> {
+        let (width, height) = init_variant.default_size();
+        let (width, height) = (size.0.unwrap_or(width), size.1.unwrap_or(height));
         match (field_type, init_variant) {
-            (FieldType::Field1D, InitVariant::Field1D(i)) => Box::new(Field1D::new(i)),
-            (FieldType::Field2D, InitVariant::Field2D(i)) => Box::new(Field2D::new(vectorized, i)),
+            (FieldType::Field1D, InitVariant::Field1D(i)) => Box::new(Field1D::new(i, width, config)),
+            (FieldType::Field2D, InitVariant::Field2D(i)) => {
+                Box::new(Field2D::new(vectorized, i, (width, height), config))
+            }
             // fallback (shouldn't happen)
-            (FieldType::Field1D, _) => Box::new(Field1D::new(Field1DInit::AtEnd)),
-            (FieldType::Field2D, _) => Box::new(Field2D::new(vectorized, Field2DInit::Zero)),
+            (FieldType::Field1D, _) => Box::new(Field1D::new(Field1DInit::AtEnd, width, config)),
+            (FieldType::Field2D, _) => Box::new(Field2D::new(vectorized, Field2DInit::Zero, (width, height), config)),
+        }
+    }
+
+    fn init_renderer(field_type: FieldType) -> Box<dyn FieldRenderer> {
+        match field_type {
+            FieldType::Field1D => Box::new(Field1DRenderer::new()),
+            FieldType::Field2D => Box::new(Field2DRenderer::new()),
         }
     }
 
     pub fn update(&mut self) {
         let start = Instant::now();
+        self.just_updated = false;
+        // the picker takes all input while it's open, and the field waits
+        if let Some(picker) = &mut self.picker {
+            match picker.update() {
+                Pick::Browsing => {}
+                Pick::Cancelled => {
+                    self.picker = None;
+                    self.swallow_click = true;
+                }
+                Pick::Chosen(path) => {
+                    self.picker = None;
+                    self.swallow_click = true;
+                    self.load_scenario(&path);
+                }
+            }
+            self.update_duration = start.elapsed();
+            return;
+        }
+        if !is_mouse_button_down(MouseButton::Left) {
+            self.swallow_click = false;
+        }
         if is_key_pressed(KeyCode::Escape) {
             quit();
         }
@@ -97,46 +264,569 @@ impl Game {
         }
         if is_key_pressed(KeyCode::N) {
             self.init_variant = self.init_variant.cycle();
-            self.field = Self::init_field(self.field_type, false, self.init_variant);
-            self.step = 0;
+            self.scenario = None;
+            self.reset(false);
         } else if is_key_pressed(KeyCode::R) {
-            self.field = Self::init_field(self.field_type, false, self.init_variant);
-            self.step = 0;
+            self.reset(false);
         } else if is_key_pressed(KeyCode::T) {
-            self.field = Self::init_field(self.field_type, true, self.init_variant);
-            self.step = 0;
+            self.reset(true);
+        } else if is_key_pressed(KeyCode::PageUp) || is_key_pressed(KeyCode::PageDown) {
+            // shift only changes the width, which makes non-square domains
+            let (width, height) = self.grid_size();
+            let grow = is_key_pressed(KeyCode::PageUp);
+            let scale = |n: usize| if grow { n * 2 } else { n / 2 }.clamp(MIN_GRID_SIZE, MAX_GRID_SIZE);
+            self.width = Some(scale(width));
+            if !shift_down() {
+                self.height = Some(scale(height));
+            }
+            // scenarios are laid out in cells, so resizing goes back to the presets
+            self.scenario = None;
+            self.reset(false);
+        } else if is_key_pressed(KeyCode::Home) {
+            self.width = None;
+            self.height = None;
+            self.scenario = None;
+            self.reset(false);
+        } else if is_key_pressed(KeyCode::O) {
+            let current = self.scenario.as_ref().map(|scenario| scenario.path.as_path());
+            match ScenarioPicker::open(current) {
+                Ok(picker) => self.picker = Some(picker),
+                Err(err) => self.fail(err),
+            }
+        } else if is_key_pressed(KeyCode::P) {
+            self.toggle_recording();
+        } else if is_key_pressed(KeyCode::Z) {
+            self.reversed = !self.reversed;
+        } else if is_key_pressed(KeyCode::G) {
+            self.toggle_gif();
+        } else if is_key_pressed(KeyCode::F5) {
+            self.save_snapshot();
+        } else if is_key_pressed(KeyCode::F9) {
+            match Self::latest_snapshot() {
+                Some(path) => self.load_snapshot(&path),
+                None => self.fail(format!("no snapshots in {SNAPSHOT_DIR}")),
+            }
+        }
+
+        let config = self.config;
+        if is_key_pressed(KeyCode::B) {
+            self.config.boundary = self.config.boundary.cycle();
+        }
+        if is_key_pressed(KeyCode::M) {
+            self.config.medium = self.config.medium.cycle();
+        }
+        if is_key_pressed(KeyCode::D) {
+            self.config.damping = if shift_down() {
+                let damping = self.config.damping / 2.;
+                if damping < MIN_DAMPING {
+                    0.
+                } else {
+                    damping
+                }
+            } else {
+                (self.config.damping * 2.).clamp(MIN_DAMPING, MAX_DAMPING)
+            };
+        }
+        if is_key_pressed(KeyCode::I) {
+            self.config.integrator = self.config.integrator.cycle();
         }
 
         if is_key_pressed(KeyCode::Equal) {
-            self.updates_per_frame = (self.updates_per_frame * 2).min(MAX_UPDATES_PER_FRAME);
+            if shift_down() {
+                self.config.dt = (self.config.dt * 2.).min(MAX_DT);
+            } else {
+                self.updates_per_frame = (self.updates_per_frame * 2).min(MAX_UPDATES_PER_FRAME);
+            }
         } else if is_key_pressed(KeyCode::Minus) {
-            self.updates_per_frame = (self.updates_per_frame / 2).max(MIN_UPDATES_PER_FRAME);
+            if shift_down() {
+                self.config.dt = (self.config.dt / 2.).max(MIN_DT);
+            } else {
+                self.updates_per_frame = (self.updates_per_frame / 2).max(MIN_UPDATES_PER_FRAME);
+            }
+        }
+        if self.config != config {
+            self.field.configure(self.config);
+        }
+
+        if is_key_pressed(KeyCode::C) {
+            self.colors.colormap = self.colors.colormap.cycle();
+        }
+        if is_key_pressed(KeyCode::A) {
+            self.auto_range = !self.auto_range;
+        }
+        // adjusting the range by hand starts from the fitted one and stops fitting it
+        if is_key_pressed(KeyCode::Key9) {
+            self.auto_range = false;
+            self.colors.range = self.colors.range.scaled(0.5);
+        } else if is_key_pressed(KeyCode::Key0) {
+            self.auto_range = false;
+            self.colors.range = self.colors.range.scaled(2.);
+        }
+
+        if is_key_pressed(KeyCode::RightBracket) {
+            self.disturbance.radius = (self.disturbance.radius + 1.).min(MAX_DISTURBANCE_RADIUS);
+        } else if is_key_pressed(KeyCode::LeftBracket) {
+            self.disturbance.radius = (self.disturbance.radius - 1.).max(MIN_DISTURBANCE_RADIUS);
+        }
+        if is_key_pressed(KeyCode::Apostrophe) {
+            self.disturbance.amplitude = (self.disturbance.amplitude * 2.).min(MAX_DISTURBANCE_AMPLITUDE);
+        } else if is_key_pressed(KeyCode::Semicolon) {
+            self.disturbance.amplitude = (self.disturbance.amplitude / 2.).max(MIN_DISTURBANCE_AMPLITUDE);
         }
 
+        let mouse: Vec2 = mouse_position().into();
+        let mouse_cell = self.renderer.screen_to_cell(self.field.as_ref(), mouse);
+        let view = self.renderer.view(self.field.as_ref());
         if is_mouse_button_pressed(MouseButton::Left) {
-            // self.field.(mouse_position());
+            self.scrubbing = self.rewind.position_at(view, mouse, false).is_some();
+            if self.scrubbing {
+                self.state = GameState::Paused;
+            }
+            self.section_anchor = mouse_cell.filter(|_| shift_down());
+            if let Some(cell) = mouse_cell.filter(|_| ctrl_down()) {
+                if let Err(err) = self.probes.toggle((cell.x as usize, cell.y as usize)) {
+                    self.fail(err);
+                }
+            }
+        }
+        if is_mouse_button_down(MouseButton::Left) && self.scrubbing {
+            if let Some(position) = self.rewind.position_at(view, mouse, true) {
+                self.seek(position);
+            }
+        } else if let (true, Some(anchor)) = (is_mouse_button_down(MouseButton::Left), self.section_anchor) {
+            if let Some(cell) = mouse_cell.filter(|&cell| cell != anchor) {
+                self.renderer.set_section(Some((anchor, cell)));
+            }
+        } else if is_mouse_button_down(MouseButton::Left) && !ctrl_down() && !self.swallow_click {
+            self.drag_disturbance(mouse);
+        } else {
+            self.last_disturbance = None;
+            self.scrubbing = false;
+            self.section_anchor = None;
+        }
+        // straight sections through the cell under the mouse, or the middle of the field
+        if is_key_pressed(KeyCode::H) || is_key_pressed(KeyCode::V) {
+            let (width, height) = self.grid_size();
+            let (width, height) = (width as f32, height as f32);
+            let through = mouse_cell.map_or(vec2(width / 2., height / 2.), |cell| cell.floor() + 0.5);
+            let section = if is_key_pressed(KeyCode::H) {
+                (vec2(0.5, through.y), vec2(width - 0.5, through.y))
+            } else {
+                (vec2(through.x, 0.5), vec2(through.x, height - 0.5))
+            };
+            self.renderer.set_section(Some(section));
         }
 
-        let should_update = if self.state == GameState::Running {
-            true
-        } else if is_key_pressed(KeyCode::Period) {
-            true
+        let (_, wheel) = mouse_wheel();
+        if wheel != 0. && mouse_cell.is_some() {
+            let factor = if wheel > 0. { ZOOM_STEP } else { 1. / ZOOM_STEP };
+            self.renderer.zoom(self.field.as_ref(), mouse, factor);
+        }
+        if is_mouse_button_down(MouseButton::Middle) {
+            if let Some(from) = self.pan_from {
+                self.renderer.pan(self.field.as_ref(), mouse - from);
+            }
+            self.pan_from = Some(mouse);
         } else {
-            false
-        };
+            self.pan_from = None;
+        }
+        if is_key_pressed(KeyCode::F) {
+            self.renderer.fit();
+        }
+        if is_key_pressed(KeyCode::S) {
+            if let Err(err) = self.renderer.toggle_spectrum() {
+                self.fail(err);
+            }
+        }
+
+        if is_key_pressed(KeyCode::K) {
+            self.source_kind = self.source_kind.cycle();
+        }
+        if is_key_pressed(KeyCode::X) {
+            self.field.clear_sources();
+            self.edited();
+        }
+        if is_key_pressed(KeyCode::U) {
+            self.probes.clear();
+        }
+        if is_key_pressed(KeyCode::E) {
+            self.export_probes();
+        }
+        let mouse_cell = mouse_cell.map(|cell| (cell.x as usize, cell.y as usize));
+        if is_mouse_button_pressed(MouseButton::Right) {
+            self.source_anchor = mouse_cell;
+        }
+        if is_mouse_button_released(MouseButton::Right) {
+            // point sources go where the button went down, lines span the drag
+            if let (Some(from), Some(to)) = (self.source_anchor.take(), mouse_cell) {
+                let source = self
+                    .source_kind
+                    .place(from, to, self.disturbance.amplitude, self.field.step());
+                self.field.add_source(source);
+                self.edited();
+            }
+        }
+
+        if self.rewind.is_empty() {
+            self.rewind.record(self.field.as_ref());
+        }
+        if is_key_pressed(KeyCode::Comma) && self.rewind.position() > 0 {
+            self.state = GameState::Paused;
+            self.seek(self.rewind.position() - 1);
+        }
+        // stepping forward after rewinding replays the saved states before simulating new ones
+        let replay =
+            !self.reversed && self.state == GameState::Paused && self.rewind.rewound() && is_key_pressed(KeyCode::Period);
+        if replay {
+            self.seek(self.rewind.position() + 1);
+        }
+        let should_update = !replay && (self.state == GameState::Running || is_key_pressed(KeyCode::Period));
         if should_update {
             for _ in 0..self.updates_per_frame {
-                self.field.update();
-                self.step += 1;
+                if self.reversed {
+                    // the start is where the reversed state can be compared to the original, and
+                    // without one the field hasn't run forward yet
+                    if self.origin.as_ref().is_none_or(|origin| self.field.step() <= origin.step) {
+                        self.state = GameState::Paused;
+                        break;
+                    }
+                    self.field.update_backwards();
+                    // the probes only keep samples of the way forward
+                    self.probes.truncate_after(self.field.step());
+                } else {
+                    // taken now rather than at the reset, so that edits made before running count
+                    if self.origin.is_none() {
+                        self.origin = Some(Origin {
+                            step: self.field.step(),
+                            u: self.field.u().to_owned(),
+                            v: self.field.v().to_owned(),
+                        });
+                    }
+                    self.field.update();
+                    self.probes.record(self.field.as_ref());
+                }
+                self.capture_gif_frame();
+                if self.field.diverged() {
+                    // stop before the state turns into NaNs for good
+                    self.state = GameState::Paused;
+                    self.diverged_at.get_or_insert(self.field.step());
+                    break;
+                }
+            }
+            if self.reversed {
+                // the history goes on from the earlier state after turning time around again
+                self.rewind.truncate_after(self.field.step());
+            } else {
+                self.rewind.record(self.field.as_ref());
             }
         }
-        self.just_updated = should_update;
+        self.just_updated |= should_update;
         self.update_duration = start.elapsed();
     }
 
+    /// Rebuild the field from the current scenario or init, size and settings.
+    fn reset(&mut self, vectorized: bool) {
+        if let Some(scenario) = &self.scenario {
+            match scenario.build(self.config, vectorized) {
+                Ok(field) => self.field = field,
+                Err(err) => {
+                    self.fail(err);
+                    return;
+                }
+            }
+        } else {
+            let size = (self.width, self.height);
+            self.field = Self::init_field(self.field_type, vectorized, self.init_variant, size, self.config);
+        }
+        self.vectorized = vectorized;
+        self.diverged_at = None;
+        self.rewind.clear();
+        self.origin = None;
+        self.probes.clear_samples();
+    }
+
+    /// How far the field is from the state it started from, as the L2 norm of the difference in
+    /// `(u, v)` relative to that of the starting state, along with the step it started at.
+    pub fn origin_error(&self) -> Option<(u32, f32)> {
+        let origin = self.origin.as_ref()?;
+        let norm2 = |a: &ArrayD<f32>| a.iter().map(|x| x * x).sum::<f32>();
+        let diff2 = |a: ndarray::ArrayViewD<'_, f32>, b: &ArrayD<f32>| {
+            a.iter().zip(b.iter()).map(|(x, y)| (x - y) * (x - y)).sum::<f32>()
+        };
+        let diff = diff2(self.field.u(), &origin.u) + diff2(self.field.v(), &origin.v);
+        let norm = norm2(&origin.u) + norm2(&origin.v);
+        Some((origin.step, if norm > 0. { (diff / norm).sqrt() } else { diff.sqrt() }))
+    }
+
+    /// Go back or forth to the state at `position` in the rewind history.
+    fn seek(&mut self, position: usize) {
+        if position == self.rewind.position() {
+            return;
+        }
+        match self.rewind.seek(self.field.as_mut(), position) {
+            Ok(step) => {
+                self.probes.truncate_after(step);
+                if self.diverged_at.is_some_and(|diverged_at| diverged_at > step) {
+                    self.diverged_at = None;
+                }
+                self.just_updated = true;
+            }
+            Err(err) => self.fail(err),
+        }
+    }
+
+    fn fail(&mut self, error: String) {
+        self.error = Some(error);
+        self.notice = None;
+    }
+
+    fn succeed(&mut self, notice: String) {
+        self.notice = Some(notice);
+        self.error = None;
+    }
+
+    /// Switch between 1D and 2D, keeping the init if it fits the new kind of field.
+    fn set_field_type(&mut self, field_type: FieldType) {
+        self.field_type = field_type;
+        if !matches!(
+            (field_type, self.init_variant),
+            (FieldType::Field1D, InitVariant::Field1D(_)) | (FieldType::Field2D, InitVariant::Field2D(_))
+        ) {
+            self.init_variant = match field_type {
+                FieldType::Field1D => InitVariant::Field1D(Field1DInit::AtEnd),
+                FieldType::Field2D => InitVariant::Field2D(Field2DInit::Standing),
+            };
+        }
+        self.renderer = Self::init_renderer(field_type);
+    }
+
+    fn toggle_recording(&mut self) {
+        match self.recorder.take() {
+            Some(recorder) => {
+                self.succeed(format!("Recorded {} frames to {}", recorder.written, recorder.dir.display()))
+            }
+            None => match Recorder::start(self.record_every, self.record_scale) {
+                Ok(recorder) => {
+                    self.succeed(format!("Recording to {}", recorder.dir.display()));
+                    self.recorder = Some(recorder);
+                }
+                Err(err) => self.fail(err),
+            },
+        }
+    }
+
+    /// Start writing a GIF to the first unused `GIF_DIR/NNN.gif`, or finish the one in progress.
+    fn toggle_gif(&mut self) {
+        if let Some(gif) = self.gif.take() {
+            self.finish_gif(gif);
+            return;
+        }
+        let path = (0..)
+            .map(|n| Path::new(GIF_DIR).join(format!("{n:03}.gif")))
+            .find(|path| !path.exists())
+            .unwrap();
+        let started = std::fs::create_dir_all(GIF_DIR)
+            .map_err(|err| format!("{GIF_DIR}: {err}"))
+            .and_then(|_| GifWriter::create(&path, self.field.as_ref(), self.gif_options));
+        match started {
+            Ok(mut gif) => match gif.add_frame(self.field.as_ref()) {
+                Ok(()) => {
+                    self.succeed(format!("Recording GIF to {}", path.display()));
+                    self.gif = Some(gif);
+                }
+                Err(err) => self.fail(err),
+            },
+            Err(err) => self.fail(err),
+        }
+    }
+
+    /// Add the field to the GIF in progress if it's at a step that gets a frame, and finish
+    /// the GIF once it's long enough.
+    fn capture_gif_frame(&mut self) {
+        let Some(gif) = &mut self.gif else {
+            return;
+        };
+        if let Err(err) = gif.capture(self.field.as_ref()) {
+            self.gif = None;
+            self.fail(err);
+        } else if gif.done() {
+            let gif = self.gif.take().unwrap();
+            self.finish_gif(gif);
+        }
+    }
+
+    fn finish_gif(&mut self, gif: GifWriter) {
+        let notice = format!("Recorded {} frames to {}", gif.written, gif.path.display());
+        match gif.finish() {
+            Ok(()) => self.succeed(notice),
+            Err(err) => self.fail(err),
+        }
+    }
+
+    /// Write what the probes recorded to the first unused `PROBE_DIR/probes_NNN.csv`.
+    fn export_probes(&mut self) {
+        if self.probes.is_empty() {
+            self.fail("no probes to export, place some with ctrl+click".to_string());
+            return;
+        }
+        let path = (0..)
+            .map(|n| Path::new(PROBE_DIR).join(format!("probes_{n:03}.csv")))
+            .find(|path| !path.exists())
+            .unwrap();
+        let written = std::fs::create_dir_all(PROBE_DIR)
+            .map_err(|err| format!("{PROBE_DIR}: {err}"))
+            .and_then(|_| self.probes.write_csv(&path));
+        match written {
+            Ok(()) => self.succeed(format!("Exported probes to {}", path.display())),
+            Err(err) => self.fail(err),
+        }
+    }
+
+    /// Save the full state to `SNAPSHOT_DIR`, named after the current step.
+    fn save_snapshot(&mut self) {
+        let path = Path::new(SNAPSHOT_DIR).join(format!("step_{:06}.npz", self.field.step()));
+        let saved = std::fs::create_dir_all(SNAPSHOT_DIR)
+            .map_err(|err| format!("{SNAPSHOT_DIR}: {err}"))
+            .and_then(|_| Snapshot::capture(self.field.as_ref(), self.config, self.vectorized).save(&path));
+        match saved {
+            Ok(()) => self.succeed(format!("Saved {}", path.display())),
+            Err(err) => self.fail(err),
+        }
+    }
+
+    /// Resume from the snapshot at `path`, taking over its settings. On failure the current
+    /// field stays and the error is kept for display.
+    pub fn load_snapshot(&mut self, path: &Path) {
+        let restored = Snapshot::load(path).and_then(|snapshot| {
+            let field = snapshot.restore(snapshot.config, snapshot.vectorized)?;
+            Ok((field, snapshot))
+        });
+        let (field, snapshot) = match restored {
+            Ok(restored) => restored,
+            Err(err) => {
+                self.fail(err);
+                return;
+            }
+        };
+        self.set_field_type(if snapshot.mask.is_some() { FieldType::Field2D } else { FieldType::Field1D });
+        self.field = field;
+        self.config = snapshot.config;
+        self.vectorized = snapshot.vectorized;
+        self.diverged_at = None;
+        self.rewind.clear();
+        self.origin = None;
+        self.probes.clear_samples();
+        self.state = GameState::Paused;
+        self.succeed(format!("Loaded {}", path.display()));
+    }
+
+    /// Most recently written snapshot in `SNAPSHOT_DIR`.
+    fn latest_snapshot() -> Option<PathBuf> {
+        std::fs::read_dir(SNAPSHOT_DIR)
+            .ok()?
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| entry.path().extension().is_some_and(|ext| ext == "npz"))
+            .max_by_key(|entry| entry.metadata().and_then(|meta| meta.modified()).ok())
+            .map(|entry| entry.path())
+    }
+
+    /// Set the field up from the scenario file at `path`, taking over its settings. On failure
+    /// the current field stays and the error is kept for display.
+    pub fn load_scenario(&mut self, path: &Path) {
+        let scenario = match Scenario::load(path) {
+            Ok(scenario) => scenario,
+            Err(err) => {
+                self.fail(err);
+                return;
+            }
+        };
+        self.set_field_type(if scenario.dims == 1 { FieldType::Field1D } else { FieldType::Field2D });
+        self.config = scenario.config.into();
+        self.updates_per_frame =
+            scenario.view.updates_per_frame.clamp(MIN_UPDATES_PER_FRAME, MAX_UPDATES_PER_FRAME);
+        self.state = if scenario.view.running { GameState::Running } else { GameState::Paused };
+        self.succeed(format!("Loaded {}", scenario.path.display()));
+        self.scenario = Some(scenario);
+        self.reset(false);
+    }
+
+    /// Width and height of the current field, 1D fields having a height of 1.
+    pub fn grid_size(&self) -> (usize, usize) {
+        let u = self.field.u();
+        let shape = u.shape();
+        (shape[0], shape.get(1).copied().unwrap_or(1))
+    }
+
+    /// Disturb the field under the mouse. While dragging, bumps are laid along the path one
+    /// radius apart, so fast drags leave a continuous trail and a still mouse doesn't pile up.
+    fn drag_disturbance(&mut self, pos: Vec2) {
+        let Some(cell) = self.renderer.screen_to_cell(self.field.as_ref(), pos) else {
+            self.last_disturbance = None;
+            return;
+        };
+        let Some(mut last) = self.last_disturbance else {
+            self.field.add_disturbance(cell.into(), self.disturbance);
+            self.last_disturbance = Some(cell);
+            self.edited();
+            return;
+        };
+        let spacing = self.disturbance.radius;
+        if last.distance(cell) < spacing {
+            return;
+        }
+        while last.distance(cell) >= spacing {
+            last += (cell - last).normalize() * spacing;
+            self.field.add_disturbance(last.into(), self.disturbance);
+        }
+        self.last_disturbance = Some(last);
+        self.edited();
+    }
+
+    /// Keep the rewind history in line with a change made to the field by hand, which makes the
+    /// edited state the latest one.
+    fn edited(&mut self) {
+        self.rewind.branch(self.field.as_ref());
+        // an edit at the start changes the state running backwards should come back to
+        if self.origin.as_ref().is_some_and(|origin| origin.step == self.field.step()) {
+            self.origin = None;
+        }
+    }
+
     pub fn render(&mut self) {
         let start = Instant::now();
-        self.field.render();
+        if self.auto_range {
+            self.colors.range = ColorRange::fit(self.field.as_ref());
+        }
+        self.renderer.render(self.field.as_ref(), self.colors);
+        // mark the probes with their number in the color of their charts
+        for (i, probe) in self.probes.iter().enumerate() {
+            let (x, y) = probe.cell;
+            let center = vec2(x as f32 + 0.5, y as f32 + 0.5);
+            if let Some(pos) = self.renderer.cell_to_screen(self.field.as_ref(), center) {
+                draw_circle(pos.x, pos.y, 5., BLACK);
+                draw_circle(pos.x, pos.y, 4., probe_color(i));
+                draw_text(&(i + 1).to_string(), pos.x + 6., pos.y - 4., 16., BLACK);
+            }
+        }
+        self.rewind.draw(self.renderer.view(self.field.as_ref()));
+        if let Some(picker) = &self.picker {
+            picker.draw();
+        }
+        // frames the field didn't change in, e.g. while paused, would only repeat the last one
+        if let Some(recorder) = self.recorder.as_mut().filter(|_| self.just_updated) {
+            if let Err(err) = recorder.capture(self.renderer.as_ref()) {
+                self.recorder = None;
+                self.fail(err);
+            }
+        }
         self.rendering_duration = start.elapsed();
     }
 }
+
+fn shift_down() -> bool {
+    is_key_down(KeyCode::LeftShift) || is_key_down(KeyCode::RightShift)
+}
+
+fn ctrl_down() -> bool {
+    is_key_down(KeyCode::LeftControl) || is_key_down(KeyCode::RightControl)
+}
@@ -0,0 +1,53 @@
+//! Looking up the settings enums by the labels the app shows them with, as command line
+//! options, scenario files and snapshots name them.
+
+use crate::colormap::Colormap;
+use crate::field::{Boundary, Field1DInit, Field2DInit, Integrator, Medium};
+
+/// Enums selectable by the label they're shown with in the app.
+pub trait Labeled: Copy + 'static {
+    /// variant the cycle starts from
+    const FIRST: Self;
+    fn cycle(self) -> Self;
+    fn label(self) -> &'static str;
+}
+
+macro_rules! labeled {
+    ($($t:ty => $first:expr),* $(,)?) => {
+        $(impl Labeled for $t {
+            const FIRST: Self = $first;
+            fn cycle(self) -> Self {
+                <$t>::cycle(self)
+            }
+            fn label(self) -> &'static str {
+                <$t>::label(self)
+            }
+        })*
+    };
+}
+
+labeled! {
+    Boundary => Boundary::Reflective,
+    Medium => Medium::Uniform,
+    Integrator => Integrator::SymplecticEuler,
+    Field1DInit => Field1DInit::AtEnd,
+    Field2DInit => Field2DInit::Zero,
+    Colormap => Colormap::Classic,
+}
+
+/// Find the variant labeled `name`, where dashes stand in for spaces, e.g. `double-slit`.
+pub fn from_label<T: Labeled>(name: &str) -> Result<T, String> {
+    let name = name.replace('-', " ");
+    let mut variant = T::FIRST;
+    let mut labels = vec![];
+    loop {
+        if variant.label() == name {
+            return Ok(variant);
+        }
+        labels.push(variant.label().replace(' ', "-"));
+        variant = variant.cycle();
+        if variant.label() == T::FIRST.label() {
+            return Err(format!("unknown value `{name}`, expected one of: {}", labels.join(", ")));
+        }
+    }
+}
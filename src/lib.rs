@@ -0,0 +1,8 @@
+pub mod animation;
+pub mod colormap;
+pub mod field;
+pub mod label;
+pub mod probe;
+pub mod scenario;
+pub mod snapshot;
+pub mod spectrum;
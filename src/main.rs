@@ -2,15 +2,18 @@ use macroquad::prelude::*;
 
 mod control_panel;
 mod debugger;
-mod field;
 mod game;
 mod monitor;
 mod observer;
+mod picker;
+mod render;
+mod rewind;
 
 use control_panel::ControlPanel;
 use debugger::Debugger;
 use game::{FieldType, Game};
 use observer::Observer;
+use pixel_wave_simulator::{animation, colormap, field, label, probe, scenario, snapshot, spectrum};
 
 fn window_conf() -> macroquad::window::Conf {
     Conf {
@@ -19,9 +22,17 @@ fn window_conf() -> macroquad::window::Conf {
     }
 }
 
+/// Value of the option `name`, given as `name=value` or `name value`.
+fn arg_value(args: &[String], name: &str) -> Option<String> {
+    args.iter().enumerate().find_map(|(i, arg)| match arg.strip_prefix(name)? {
+        "" => args.get(i + 1).cloned(),
+        rest => rest.strip_prefix('=').map(str::to_string),
+    })
+}
+
 #[macroquad::main(window_conf)]
 async fn main() {
-    let args: Vec<String> = std::env::args().into_iter().collect();
+    let args: Vec<String> = std::env::args().collect();
 
     let field_type = if args.contains(&"--dims=1".to_string()) {
         FieldType::Field1D
@@ -29,7 +40,37 @@ async fn main() {
         FieldType::Field2D
     };
 
-    let mut game = Game::new(field_type);
+    // e.g. --width=256 --height=64, each defaulting to the size the init was designed at
+    let size_arg = |name: &str| arg_value(&args, name)?.parse().ok();
+
+    let mut game = Game::new(field_type, size_arg("--width"), size_arg("--height"));
+    if let Some(path) = arg_value(&args, "--scenario") {
+        game.load_scenario(path.as_ref());
+    }
+    if let Some(path) = arg_value(&args, "--snapshot") {
+        game.load_snapshot(path.as_ref());
+    }
+    // recording with P writes every Nth frame, upscaled by an integer factor
+    if let Some(every) = arg_value(&args, "--record-every").and_then(|every| every.parse().ok()) {
+        game.record_every = every;
+    }
+    if let Some(scale) = arg_value(&args, "--record-scale").and_then(|scale| scale.parse().ok()) {
+        game.record_scale = scale;
+    }
+    if let Some(name) = arg_value(&args, "--colormap") {
+        match label::from_label(&name) {
+            Ok(colormap) => game.colors.colormap = colormap,
+            Err(err) => eprintln!("{err} for --colormap"),
+        }
+    }
+    // recording a GIF with G, e.g. --gif-every=2 --gif-delay=3 --gif-frames=200 --gif-palette=viridis
+    for key in ["every", "delay", "frames", "range", "scale", "palette"] {
+        if let Some(value) = arg_value(&args, &format!("--gif-{key}")) {
+            if let Err(err) = game.gif_options.set(key, &value) {
+                eprintln!("{err}");
+            }
+        }
+    }
     let mut observers: Vec<Box<dyn Observer>> =
         vec![Box::new(Debugger::new()), Box::new(ControlPanel::new())];
 
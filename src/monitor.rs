@@ -1,3 +1,5 @@
+use std::collections::VecDeque;
+
 pub struct Monitor {
     pub display_val: f32,
     acc: f32,
@@ -26,3 +28,40 @@ impl Monitor {
     }
 }
 
+/// Fixed-size window of the most recent samples of a value, oldest first.
+pub struct History {
+    samples: VecDeque<f32>,
+    capacity: usize,
+}
+
+impl History {
+    pub fn new(capacity: usize) -> Self {
+        History {
+            samples: VecDeque::with_capacity(capacity),
+            capacity,
+        }
+    }
+
+    pub fn push(&mut self, val: f32) {
+        if self.samples.len() == self.capacity {
+            self.samples.pop_front();
+        }
+        self.samples.push_back(val);
+    }
+
+    pub fn clear(&mut self) {
+        self.samples.clear();
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = f32> + '_ {
+        self.samples.iter().copied()
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    pub fn max(&self) -> f32 {
+        self.iter().fold(0., f32::max)
+    }
+}
@@ -0,0 +1,140 @@
+use std::path::{Path, PathBuf};
+
+use macroquad::prelude::*;
+
+/// name of the directories scenario files are looked for in
+const SCENARIO_DIR: &str = "scenarios";
+
+/// where the list is drawn, over the field
+const PICKER_X: f32 = 50.0;
+const PICKER_Y: f32 = 50.0;
+const PICKER_WIDTH: f32 = 400.0;
+const ROW_HEIGHT: f32 = 20.0;
+const FONT_SIZE: f32 = 16.0;
+/// most entries shown at once, the list scrolls to keep the selected one in view
+const MAX_ROWS: usize = 16;
+
+/// What the user did with the picker this frame.
+pub enum Pick {
+    Browsing,
+    Cancelled,
+    Chosen(PathBuf),
+}
+
+/// List of the scenario files found on disk to choose one from, with the arrow keys and Enter or
+/// the mouse.
+pub struct ScenarioPicker {
+    /// scenario files, grouped by directory in the order they were searched and sorted by name
+    paths: Vec<PathBuf>,
+    selected: usize,
+    /// first entry shown
+    scroll: usize,
+    /// mouse position at the last frame, the selection only follows the mouse when it moves
+    mouse: Vec2,
+}
+
+impl ScenarioPicker {
+    /// Look for scenario files next to `current`, the scenario loaded now, in `SCENARIO_DIR`
+    /// under the working directory, and in `SCENARIO_DIR` next to the executable or any of its
+    /// parents, so a built binary finds the repository's own. Fails if there are none.
+    pub fn open(current: Option<&Path>) -> Result<Self, String> {
+        let canonical = |path: &Path| path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+        // a bare file name's parent is empty, which stands for the working directory
+        let parent = current.and_then(Path::parent).map(|dir| if dir == Path::new("") { Path::new(".") } else { dir });
+        let mut dirs: Vec<PathBuf> = parent.map(Path::to_path_buf).into_iter().collect();
+        dirs.push(PathBuf::from(SCENARIO_DIR));
+        if let Ok(exe) = std::env::current_exe() {
+            dirs.extend(exe.ancestors().skip(1).map(|dir| dir.join(SCENARIO_DIR)));
+        }
+        let mut seen = vec![];
+        let mut paths = vec![];
+        for dir in dirs {
+            let Ok(entries) = std::fs::read_dir(&dir) else {
+                continue;
+            };
+            let mut found: Vec<PathBuf> = entries
+                .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+                .filter(|path| path.extension().is_some_and(|ext| ext == "toml" || ext == "json"))
+                .collect();
+            found.sort();
+            for path in found {
+                // the same directory can turn up through several of the places searched
+                let canonical = canonical(&path);
+                if !seen.contains(&canonical) {
+                    seen.push(canonical);
+                    paths.push(path);
+                }
+            }
+        }
+        if paths.is_empty() {
+            return Err(format!("no scenarios found in `{SCENARIO_DIR}` here or next to the executable"));
+        }
+        let current = current.map(canonical);
+        let selected = current.and_then(|current| seen.iter().position(|path| *path == current)).unwrap_or(0);
+        Ok(ScenarioPicker {
+            paths,
+            selected,
+            scroll: selected.saturating_sub(MAX_ROWS - 1),
+            mouse: mouse_position().into(),
+        })
+    }
+
+    /// Move the selection with the arrow keys or the mouse, choose with Enter or a click and
+    /// cancel with Escape or a click outside the list.
+    pub fn update(&mut self) -> Pick {
+        if is_key_pressed(KeyCode::Escape) {
+            return Pick::Cancelled;
+        }
+        if is_key_pressed(KeyCode::Up) {
+            self.selected = self.selected.checked_sub(1).unwrap_or(self.paths.len() - 1);
+        }
+        if is_key_pressed(KeyCode::Down) {
+            self.selected = (self.selected + 1) % self.paths.len();
+        }
+        let mouse: Vec2 = mouse_position().into();
+        let hovered = self.row_at(mouse);
+        if let Some(row) = hovered.filter(|_| mouse != self.mouse) {
+            self.selected = row;
+        }
+        self.mouse = mouse;
+        self.scroll = self.scroll.clamp(self.selected.saturating_sub(MAX_ROWS - 1), self.selected);
+        let clicked = is_mouse_button_pressed(MouseButton::Left);
+        if clicked && hovered.is_none() {
+            return Pick::Cancelled;
+        }
+        if let Some(row) = hovered.filter(|_| clicked) {
+            return Pick::Chosen(self.paths[row].clone());
+        }
+        if is_key_pressed(KeyCode::Enter) || is_key_pressed(KeyCode::KpEnter) {
+            return Pick::Chosen(self.paths[self.selected].clone());
+        }
+        Pick::Browsing
+    }
+
+    /// Entry under the screen position `pos`, if any.
+    fn row_at(&self, pos: Vec2) -> Option<usize> {
+        let top = PICKER_Y + ROW_HEIGHT;
+        if pos.x < PICKER_X || pos.x >= PICKER_X + PICKER_WIDTH || pos.y < top {
+            return None;
+        }
+        let row = self.scroll + ((pos.y - top) / ROW_HEIGHT) as usize;
+        (row < self.paths.len().min(self.scroll + MAX_ROWS)).then_some(row)
+    }
+
+    /// Draw the list with a title row, the selected entry highlighted.
+    pub fn draw(&self) {
+        let rows = self.paths.len().min(MAX_ROWS);
+        draw_rectangle(PICKER_X, PICKER_Y, PICKER_WIDTH, ROW_HEIGHT * (rows + 1) as f32, WHITE);
+        draw_rectangle(PICKER_X, PICKER_Y, PICKER_WIDTH, ROW_HEIGHT, DARKGRAY);
+        let title = "Pick a scenario (Enter to load, Esc to cancel)";
+        draw_text(title, PICKER_X + 6., PICKER_Y + ROW_HEIGHT - 5., FONT_SIZE, WHITE);
+        for (row, path) in self.paths.iter().enumerate().skip(self.scroll).take(MAX_ROWS) {
+            let y = PICKER_Y + ROW_HEIGHT * (row - self.scroll + 1) as f32;
+            if row == self.selected {
+                draw_rectangle(PICKER_X, y, PICKER_WIDTH, ROW_HEIGHT, LIGHTGRAY);
+            }
+            draw_text(&path.display().to_string(), PICKER_X + 6., y + ROW_HEIGHT - 5., FONT_SIZE, BLACK);
+        }
+        draw_rectangle_lines(PICKER_X, PICKER_Y, PICKER_WIDTH, ROW_HEIGHT * (rows + 1) as f32, 2., DARKGRAY);
+    }
+}
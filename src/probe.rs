@@ -0,0 +1,183 @@
+//! Probes recording a field's displacement and velocity at single cells over time, e.g. to
+//! measure arrival times and periods.
+
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+use ndarray::ArrayViewD;
+
+use crate::field::Field;
+
+/// steps each probe remembers by default
+pub const PROBE_HISTORY: usize = 4096;
+/// most probes at once
+pub const MAX_PROBES: usize = 8;
+
+#[derive(Copy, Clone, Debug)]
+pub struct Sample {
+    pub step: u32,
+    pub u: f32,
+    pub v: f32,
+}
+
+pub struct Probe {
+    /// cell the probe sits on, `(x, 0)` in 1D fields
+    pub cell: (usize, usize),
+    /// latest samples, oldest first, one per step without gaps
+    samples: VecDeque<Sample>,
+}
+
+impl Probe {
+    pub fn samples(&self) -> impl ExactSizeIterator<Item = &Sample> + DoubleEndedIterator + Clone {
+        self.samples.iter()
+    }
+}
+
+/// A set of probes sampled together after every step.
+pub struct Probes {
+    probes: Vec<Probe>,
+    /// samples each probe keeps before dropping the oldest
+    history: usize,
+}
+
+impl Default for Probes {
+    fn default() -> Self {
+        Self::with_history(PROBE_HISTORY)
+    }
+}
+
+impl Probes {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Probes keeping the latest `history` samples each.
+    pub fn with_history(history: usize) -> Self {
+        Probes {
+            probes: vec![],
+            history: history.max(1),
+        }
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Probe> {
+        self.probes.iter()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.probes.is_empty()
+    }
+
+    /// Put a probe on `cell`, or take away the one already there. Fails once there are
+    /// `MAX_PROBES`.
+    pub fn toggle(&mut self, cell: (usize, usize)) -> Result<(), String> {
+        if let Some(i) = self.probes.iter().position(|probe| probe.cell == cell) {
+            self.probes.remove(i);
+        } else if self.probes.len() >= MAX_PROBES {
+            return Err(format!("at most {MAX_PROBES} probes"));
+        } else {
+            self.probes.push(Probe {
+                cell,
+                samples: VecDeque::new(),
+            });
+        }
+        Ok(())
+    }
+
+    pub fn clear(&mut self) {
+        self.probes.clear();
+    }
+
+    /// Forget what the probes recorded, keeping them in place, e.g. when the field is reset.
+    pub fn clear_samples(&mut self) {
+        for probe in &mut self.probes {
+            probe.samples.clear();
+        }
+    }
+
+    /// Forget the samples taken after `step`, e.g. when the field goes back to an earlier state.
+    pub fn truncate_after(&mut self, step: u32) {
+        for probe in &mut self.probes {
+            while probe.samples.back().is_some_and(|sample| sample.step > step) {
+                probe.samples.pop_back();
+            }
+        }
+    }
+
+    /// Sample `field` at every probe. Probes outside the field, e.g. after it shrank, are
+    /// skipped. Samples are taken once per step, so if the field skipped ahead of the last one,
+    /// e.g. by replaying saved states, the earlier ones are dropped.
+    pub fn record(&mut self, field: &dyn Field) {
+        let (u, v) = (field.u(), field.v());
+        for probe in &mut self.probes {
+            let (Some(u), Some(v)) = (value_at(&u, probe.cell), value_at(&v, probe.cell)) else {
+                continue;
+            };
+            if probe.samples.back().is_some_and(|sample| sample.step + 1 != field.step()) {
+                probe.samples.clear();
+            }
+            if probe.samples.len() == self.history {
+                probe.samples.pop_front();
+            }
+            probe.samples.push_back(Sample { step: field.step(), u, v });
+        }
+    }
+
+    /// Write every sample as a row of `probe,x,y,step,u,v`, probe by probe.
+    pub fn write_csv(&self, path: &Path) -> Result<(), String> {
+        let error = |err: std::io::Error| format!("{}: {err}", path.display());
+        let mut out = BufWriter::new(File::create(path).map_err(error)?);
+        let mut write = || -> std::io::Result<()> {
+            writeln!(out, "probe,x,y,step,u,v")?;
+            for (i, probe) in self.probes.iter().enumerate() {
+                let (x, y) = probe.cell;
+                for sample in &probe.samples {
+                    writeln!(out, "{i},{x},{y},{},{},{}", sample.step, sample.u, sample.v)?;
+                }
+            }
+            out.flush()
+        };
+        write().map_err(error)
+    }
+}
+
+/// Value of `values` at `cell`, ignoring y for 1D fields.
+fn value_at(values: &ArrayViewD<'_, f32>, (x, y): (usize, usize)) -> Option<f32> {
+    let index = [x, y];
+    values.get(&index[..values.ndim()]).copied()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::field::{Field1D, Field1DInit, FieldConfig};
+
+    fn steps(probes: &Probes) -> Vec<u32> {
+        probes.iter().next().unwrap().samples().map(|sample| sample.step).collect()
+    }
+
+    #[test]
+    fn gap_in_the_steps_drops_the_earlier_samples() {
+        let mut field = Field1D::new(Field1DInit::Centered, 16, FieldConfig::default());
+        let mut probes = Probes::with_history(8);
+        probes.toggle((4, 0)).unwrap();
+        for step in [0, 1, 2, 5, 6] {
+            field.set_step(step);
+            probes.record(&field);
+        }
+        assert_eq!(steps(&probes), [5, 6]);
+    }
+
+    #[test]
+    fn history_keeps_the_latest_samples() {
+        let mut field = Field1D::new(Field1DInit::Centered, 16, FieldConfig::default());
+        let mut probes = Probes::with_history(3);
+        probes.toggle((4, 0)).unwrap();
+        for step in 0..5 {
+            field.set_step(step);
+            probes.record(&field);
+        }
+        assert_eq!(steps(&probes), [2, 3, 4]);
+    }
+}
@@ -0,0 +1,75 @@
+use macroquad::prelude::*;
+
+/// fewest cells left across the longer side of the grid when zoomed all the way in
+const MIN_VISIBLE_CELLS: f32 = 8.0;
+
+/// Part of the grid a renderer shows in its view. Kept relative to the grid's size, so it
+/// stays put when the grid is resized.
+pub struct Camera {
+    /// magnification, 1 to show the whole grid
+    zoom: f32,
+    /// center of the visible part, as a fraction of the grid's width and height
+    center: Vec2,
+}
+
+impl Camera {
+    pub fn new() -> Self {
+        Camera {
+            zoom: 1.,
+            center: vec2(0.5, 0.5),
+        }
+    }
+
+    /// Show the whole grid.
+    pub fn fit(&mut self) {
+        *self = Self::new();
+    }
+
+    /// Visible part of a `grid` of cells, in cell coordinates.
+    pub fn visible(&self, grid: Vec2) -> Rect {
+        let size = grid / self.zoom;
+        let corner = (self.center * grid - size / 2.).clamp(Vec2::ZERO, grid - size);
+        Rect::new(corner.x, corner.y, size.x, size.y)
+    }
+
+    /// Cell under the screen position `pos` in a `view` showing `grid`, if any.
+    pub fn screen_to_cell(&self, grid: Vec2, view: Rect, pos: Vec2) -> Option<Vec2> {
+        let rel = (pos - view.point()) / view.size();
+        if rel.x < 0. || rel.x >= 1. || rel.y < 0. || rel.y >= 1. {
+            return None;
+        }
+        let visible = self.visible(grid);
+        Some(visible.point() + rel * visible.size())
+    }
+
+    /// Screen position of the point `cell` of `grid`, which may lie outside the `view`.
+    pub fn cell_to_screen(&self, grid: Vec2, view: Rect, cell: Vec2) -> Vec2 {
+        let visible = self.visible(grid);
+        view.point() + (cell - visible.point()) / visible.size() * view.size()
+    }
+
+    /// Zoom in by `factor`, or out for factors below 1, keeping the cell under the screen
+    /// position `pos` where it is.
+    pub fn zoom_at(&mut self, grid: Vec2, view: Rect, pos: Vec2, factor: f32) {
+        let anchor = self.screen_to_cell(grid, view, pos).unwrap_or(self.visible(grid).center());
+        let rel = ((pos - view.point()) / view.size()).clamp(Vec2::ZERO, Vec2::ONE);
+        let max_zoom = (grid.max_element() / MIN_VISIBLE_CELLS).max(1.);
+        self.zoom = (self.zoom * factor).clamp(1., max_zoom);
+        let size = grid / self.zoom;
+        self.center = (anchor - rel * size + size / 2.) / grid;
+        self.clamp(grid);
+    }
+
+    /// Move the visible part along with the mouse dragged by `delta` on screen.
+    pub fn pan(&mut self, grid: Vec2, view: Rect, delta: Vec2) {
+        let visible = self.visible(grid);
+        self.center -= delta / view.size() * visible.size() / grid;
+        self.clamp(grid);
+    }
+
+    /// Keep the visible part within the grid, so panning past an edge doesn't have to be
+    /// undone before moving back.
+    fn clamp(&mut self, grid: Vec2) {
+        self.center = self.visible(grid).center() / grid;
+    }
+}
@@ -0,0 +1,95 @@
+use macroquad::prelude::*;
+
+use super::{draw_legend, Camera, FieldRenderer};
+use crate::colormap::{medium_tint, ColorScale};
+use crate::field::Field;
+
+/// width of the whole bar, split evenly between the visible cells
+const VIEW_WIDTH: f32 = 384.0;
+const PIXEL_HEIGHT: f32 = 40.0;
+const OFFSET_X: f32 = 50.0;
+const OFFSET_Y: f32 = 50.0;
+const VIEW: Rect = Rect {
+    x: OFFSET_X,
+    y: OFFSET_Y,
+    w: VIEW_WIDTH,
+    h: PIXEL_HEIGHT,
+};
+
+pub struct Field1DRenderer {
+    camera: Camera,
+}
+
+impl FieldRenderer for Field1DRenderer {
+    fn render(&mut self, field: &dyn Field, colors: ColorScale) {
+        let u = field.u();
+        let v = field.v();
+        let c2 = field.c2();
+        let c2_max = c2.iter().cloned().fold(0., f32::max);
+        let grid = vec2(u.len() as f32, 1.);
+        // cells partly outside the view are cut off at its edges
+        let span = |j: usize| {
+            let left = self.camera.cell_to_screen(grid, VIEW, vec2(j as f32, 0.)).x.max(VIEW.x);
+            let right = self.camera.cell_to_screen(grid, VIEW, vec2(j as f32 + 1., 0.)).x.min(VIEW.right());
+            (left, right - left)
+        };
+        let visible = self.camera.visible(grid);
+        let cells = visible.x as usize..(visible.right().ceil() as usize).min(u.len());
+        for j in cells.clone() {
+            let [red, green, blue] = colors.color(u[j], v[j], medium_tint(c2[j], c2_max));
+            let (x, width) = span(j);
+            draw_rectangle(x, OFFSET_Y, width, PIXEL_HEIGHT, Color::from_rgba(red, green, blue, 255));
+        }
+        draw_legend(colors, VIEW.right() + 8., OFFSET_Y, PIXEL_HEIGHT);
+        // mark the cells driven by sources just below the field
+        for source in field.sources() {
+            for (j, _) in source.cells().into_iter().filter(|(j, _)| cells.contains(j)) {
+                let (x, width) = span(j);
+                draw_rectangle(x, OFFSET_Y + PIXEL_HEIGHT + 2., width, 6., BLACK);
+            }
+        }
+    }
+
+    fn screen_to_cell(&self, field: &dyn Field, pos: Vec2) -> Option<Vec2> {
+        let cell = self.camera.screen_to_cell(grid(field), VIEW, pos)?;
+        // the bar is a single cell high however far it's zoomed
+        Some(vec2(cell.x, (pos.y - OFFSET_Y) / PIXEL_HEIGHT))
+    }
+
+    fn cell_to_screen(&self, field: &dyn Field, cell: Vec2) -> Option<Vec2> {
+        let pos = self.camera.cell_to_screen(grid(field), VIEW, vec2(cell.x, 0.));
+        Some(vec2(pos.x, OFFSET_Y + cell.y * PIXEL_HEIGHT)).filter(|&pos| VIEW.contains(pos))
+    }
+
+    fn zoom(&mut self, field: &dyn Field, pos: Vec2, factor: f32) {
+        self.camera.zoom_at(grid(field), VIEW, pos, factor);
+    }
+
+    fn pan(&mut self, field: &dyn Field, delta: Vec2) {
+        self.camera.pan(grid(field), VIEW, delta);
+    }
+
+    fn fit(&mut self) {
+        self.camera.fit();
+    }
+
+    fn view(&self, _field: &dyn Field) -> Rect {
+        VIEW
+    }
+
+    fn frame_images(&self) -> Vec<(&'static str, &Image)> {
+        // drawn straight to the screen, there's nothing to export
+        vec![]
+    }
+}
+
+impl Field1DRenderer {
+    pub fn new() -> Self {
+        Self { camera: Camera::new() }
+    }
+}
+
+/// Size of `field`'s grid, one cell high.
+fn grid(field: &dyn Field) -> Vec2 {
+    vec2(field.u().len() as f32, 1.)
+}
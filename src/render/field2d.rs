@@ -0,0 +1,353 @@
+use std::f32::consts::PI;
+
+use macroquad::prelude::*;
+use ndarray::{ArrayView2, Ix2};
+
+use super::{draw_legend, nice_range, short_number, Camera, FieldRenderer};
+use crate::colormap::{medium_tint, ColorScale, Colormap, OBSTACLE};
+use crate::field::Field;
+use crate::spectrum::spatial_magnitude;
+
+const OFFSET_X: f32 = 50.0;
+const OFFSET_Y: f32 = 50.0;
+const VIEW_SIZE: f32 = 400.0;
+const GRAPH_HEIGHT: u16 = 128;
+/// top of the graph of the cross-section, below the timeline
+const GRAPH_Y: f32 = OFFSET_Y + 450.;
+/// on-screen height of the graph
+const GRAPH_VIEW_HEIGHT: f32 = 280.;
+const GRAPH_FONT_SIZE: f32 = 16.;
+/// colors of the u and v traces and their axes
+const U_COLOR: Color = RED;
+const V_COLOR: Color = GREEN;
+/// magnitudes shown in the spatial spectrum below its largest one
+const SPECTRUM_DECIBELS: f32 = 60.;
+/// width of the decibel bar next to the spatial spectrum
+const SPECTRUM_BAR_WIDTH: f32 = 12.;
+
+pub struct Field2DRenderer {
+    field_image: Image,
+    field_texture: Option<Texture2D>,
+    graph_image: Image,
+    graph_texture: Option<Texture2D>,
+    camera: Camera,
+    /// line the graph samples u and v along, in cell coordinates, `None` for the middle row
+    section: Option<(Vec2, Vec2)>,
+    spectrum_image: Image,
+    spectrum_texture: Option<Texture2D>,
+    /// whether the spatial spectrum takes the place of the graph
+    spectrum_shown: bool,
+}
+
+impl FieldRenderer for Field2DRenderer {
+    fn render(&mut self, field: &dyn Field, colors: ColorScale) {
+        let u = field.u().into_dimensionality::<Ix2>().unwrap();
+        let v = field.v().into_dimensionality::<Ix2>().unwrap();
+        let c2 = field.c2().into_dimensionality::<Ix2>().unwrap();
+        let mask = field.mask().map(|mask| mask.into_dimensionality::<Ix2>().unwrap());
+        let (width, height) = u.dim();
+        self.resize(width, height);
+
+        let c2_max = c2.iter().cloned().fold(0., f32::max);
+        for y in 0..height {
+            for x in 0..width {
+                let [red, green, blue] = if mask.as_ref().is_some_and(|mask| mask[(x, y)]) {
+                    OBSTACLE
+                } else {
+                    colors.color(u[(x, y)], v[(x, y)], medium_tint(c2[(x, y)], c2_max))
+                };
+                self.field_image.set_pixel(x as u32, y as u32, Color::from_rgba(red, green, blue, 255));
+            }
+        }
+        let grid = vec2(width as f32, height as f32);
+        let view = view_rect(width, height);
+        let visible = self.camera.visible(grid);
+        let field_texture = self.field_texture.get_or_insert_with(|| nearest_texture(&self.field_image));
+        field_texture.update(&self.field_image);
+        draw_texture_ex(
+            field_texture,
+            view.x,
+            view.y,
+            WHITE,
+            DrawTextureParams {
+                dest_size: Some(view.size()),
+                source: Some(visible),
+                ..Default::default()
+            },
+        );
+        draw_legend(colors, view.right() + 8., view.y, view.h);
+        // outline the visible cells driven by sources
+        let cell = view.size() / visible.size();
+        for source in field.sources() {
+            for (x, y) in source.cells() {
+                let corner = self.camera.cell_to_screen(grid, view, vec2(x as f32, y as f32));
+                if view.contains(corner) && view.contains(corner + cell - 1.) {
+                    draw_rectangle_lines(corner.x, corner.y, cell.x, cell.y, 2., WHITE);
+                }
+            }
+        }
+
+        if self.spectrum_shown {
+            self.draw_spectrum(u);
+            return;
+        }
+        let (from, to) = self.section(width, height);
+        let a = self.camera.cell_to_screen(grid, view, from);
+        let b = self.camera.cell_to_screen(grid, view, to);
+        if let Some((a, b)) = clip_segment(a, b, view) {
+            draw_line(a.x, a.y, b.x, b.y, 3., BLACK);
+            draw_line(a.x, a.y, b.x, b.y, 1., WHITE);
+        }
+
+        let cells = section_cells(from, to, width, height);
+        let us: Vec<f32> = cells.iter().map(|&cell| u[cell]).collect();
+        let vs: Vec<f32> = cells.iter().map(|&cell| v[cell]).collect();
+        let u_range = nice_range(us.iter().fold(0., |max, u| u.abs().max(max)));
+        let v_range = nice_range(vs.iter().fold(0., |max, v| v.abs().max(max)));
+        if self.graph_image.width() != cells.len() {
+            self.graph_image = Image::gen_image_color(cells.len() as u16, GRAPH_HEIGHT, BLACK);
+            self.graph_texture = None;
+        }
+        let graph_h = self.graph_image.height as u32;
+        // row of `value` on a graph spanning -range at the bottom to range at the top
+        let row = |value: f32, range: f32| ((1. - value / range) / 2. * (graph_h - 1) as f32).round() as u32;
+        for x in 0..cells.len() {
+            for gy in 0..graph_h {
+                self.graph_image.set_pixel(x as u32, gy, BLACK);
+            }
+        }
+        for x in 0..cells.len() {
+            self.graph_image.set_pixel(x as u32, row(us[x], u_range), U_COLOR);
+            self.graph_image.set_pixel(x as u32, row(vs[x], v_range), V_COLOR);
+        }
+        let graph_texture = self.graph_texture.get_or_insert_with(|| nearest_texture(&self.graph_image));
+        graph_texture.update(&self.graph_image);
+        let graph = Rect::new(OFFSET_X, GRAPH_Y, view.w, GRAPH_VIEW_HEIGHT);
+        draw_texture_ex(
+            graph_texture,
+            graph.x,
+            graph.y,
+            WHITE,
+            DrawTextureParams {
+                dest_size: Some(graph.size()),
+                ..Default::default()
+            },
+        );
+        draw_axes(graph, u_range, v_range);
+    }
+
+    fn screen_to_cell(&self, field: &dyn Field, pos: Vec2) -> Option<Vec2> {
+        let (grid, view) = grid_and_view(field);
+        self.camera.screen_to_cell(grid, view, pos)
+    }
+
+    fn cell_to_screen(&self, field: &dyn Field, cell: Vec2) -> Option<Vec2> {
+        let (grid, view) = grid_and_view(field);
+        Some(self.camera.cell_to_screen(grid, view, cell)).filter(|&pos| view.contains(pos))
+    }
+
+    fn zoom(&mut self, field: &dyn Field, pos: Vec2, factor: f32) {
+        let (grid, view) = grid_and_view(field);
+        self.camera.zoom_at(grid, view, pos, factor);
+    }
+
+    fn pan(&mut self, field: &dyn Field, delta: Vec2) {
+        let (grid, view) = grid_and_view(field);
+        self.camera.pan(grid, view, delta);
+    }
+
+    fn fit(&mut self) {
+        self.camera.fit();
+    }
+
+    fn view(&self, field: &dyn Field) -> Rect {
+        grid_and_view(field).1
+    }
+
+    fn set_section(&mut self, section: Option<(Vec2, Vec2)>) {
+        self.section = section;
+    }
+
+    fn toggle_spectrum(&mut self) -> Result<(), String> {
+        self.spectrum_shown = !self.spectrum_shown;
+        Ok(())
+    }
+
+    fn frame_images(&self) -> Vec<(&'static str, &Image)> {
+        if self.spectrum_shown {
+            vec![("field", &self.field_image), ("spectrum", &self.spectrum_image)]
+        } else {
+            vec![("field", &self.field_image), ("graph", &self.graph_image)]
+        }
+    }
+}
+
+impl Field2DRenderer {
+    pub fn new() -> Self {
+        Self {
+            field_image: Image::empty(),
+            field_texture: None,
+            graph_image: Image::empty(),
+            graph_texture: None,
+            camera: Camera::new(),
+            section: None,
+            spectrum_image: Image::empty(),
+            spectrum_texture: None,
+            spectrum_shown: false,
+        }
+    }
+
+    /// Ends of the line the graph samples, in cell coordinates.
+    fn section(&self, width: usize, height: usize) -> (Vec2, Vec2) {
+        self.section.unwrap_or_else(|| {
+            let y = (height / 2) as f32 + 0.5;
+            (vec2(0.5, y), vec2(width as f32 - 0.5, y))
+        })
+    }
+
+    /// Match the images to the field's grid, dropping textures of the old size.
+    fn resize(&mut self, width: usize, height: usize) {
+        if self.field_image.width() == width && self.field_image.height() == height {
+            return;
+        }
+        self.field_image = Image::gen_image_color(width as u16, height as u16, WHITE);
+        self.field_texture = None;
+        self.graph_image = Image::gen_image_color(width as u16, GRAPH_HEIGHT, BLACK);
+        self.graph_texture = None;
+        self.section = None;
+        self.spectrum_image = Image::gen_image_color(width as u16, height as u16, BLACK);
+        self.spectrum_texture = None;
+    }
+
+    /// Draw the magnitude of the 2D Fourier transform of `u` where the graph goes, in decibels
+    /// below its largest value, with zero wavenumber in the middle and kx from -π to π radians
+    /// per cell left to right, ky top to bottom like y on the field.
+    fn draw_spectrum(&mut self, u: ArrayView2<'_, f32>) {
+        let magnitude = spatial_magnitude(u);
+        let (width, height) = magnitude.dim();
+        let max = magnitude.iter().cloned().fold(0., f32::max);
+        for ((x, y), &m) in magnitude.indexed_iter() {
+            let decibels = if max > 0. { 20. * (m / max).log10() } else { -SPECTRUM_DECIBELS };
+            let t = (1. + decibels / SPECTRUM_DECIBELS).clamp(0., 1.) * 2. - 1.;
+            let [red, green, blue] = Colormap::Viridis.legend(t);
+            self.spectrum_image.set_pixel(x as u32, y as u32, Color::from_rgba(red, green, blue, 255));
+        }
+        let texture = self.spectrum_texture.get_or_insert_with(|| nearest_texture(&self.spectrum_image));
+        texture.update(&self.spectrum_image);
+        // square, since both axes span the same wavenumbers whatever the grid's shape
+        let panel = Rect::new(OFFSET_X, GRAPH_Y, GRAPH_VIEW_HEIGHT, GRAPH_VIEW_HEIGHT);
+        draw_texture_ex(
+            texture,
+            panel.x,
+            panel.y,
+            WHITE,
+            DrawTextureParams {
+                dest_size: Some(panel.size()),
+                ..Default::default()
+            },
+        );
+        draw_rectangle_lines(panel.x, panel.y, panel.w, panel.h, 1., BLACK);
+        // screen position of the wavenumber `k` along an axis of `len` bins spanning `size` pixels
+        let at = |k: f32, len: usize, size: f32| {
+            ((len / 2) as f32 + k * len as f32 / (2. * PI) + 0.5) / len as f32 * size
+        };
+        for (k, label) in [(-PI / 2., "-π/2"), (0., "0"), (PI / 2., "π/2")] {
+            let x = panel.x + at(k, width, panel.w);
+            draw_line(x, panel.bottom(), x, panel.bottom() + 4., 1., BLACK);
+            let label_width = measure_text(label, None, GRAPH_FONT_SIZE as u16, 1.).width;
+            draw_text(label, x - label_width / 2., panel.bottom() + GRAPH_FONT_SIZE + 2., GRAPH_FONT_SIZE, BLACK);
+            let y = panel.y + at(k, height, panel.h);
+            draw_line(panel.x - 4., y, panel.x, y, 1., BLACK);
+            draw_text(label, panel.x - 6. - label_width, y + GRAPH_FONT_SIZE / 3., GRAPH_FONT_SIZE, BLACK);
+        }
+        draw_text("kx", panel.right() - 16., panel.bottom() + GRAPH_FONT_SIZE + 2., GRAPH_FONT_SIZE, BLACK);
+        draw_text("ky", panel.x - 22., panel.bottom() - 2., GRAPH_FONT_SIZE, BLACK);
+
+        // decibel bar, 0 dB at the top
+        let bar = Rect::new(panel.right() + 12., panel.y, SPECTRUM_BAR_WIDTH, panel.h);
+        for row in 0..bar.h as u32 {
+            let t = 1. - 2. * (row as f32 + 0.5) / bar.h;
+            let [red, green, blue] = Colormap::Viridis.legend(t);
+            draw_rectangle(bar.x, bar.y + row as f32, bar.w, 1., Color::from_rgba(red, green, blue, 255));
+        }
+        draw_rectangle_lines(bar.x, bar.y, bar.w, bar.h, 1., BLACK);
+        draw_text("0 dB", bar.right() + 4., bar.y + GRAPH_FONT_SIZE / 2., GRAPH_FONT_SIZE, BLACK);
+        let bottom = format!("-{SPECTRUM_DECIBELS} dB");
+        draw_text(&bottom, bar.right() + 4., bar.bottom(), GRAPH_FONT_SIZE, BLACK);
+        draw_text("|FFT u|", bar.right() + 4., bar.center().y, GRAPH_FONT_SIZE, BLACK);
+    }
+}
+
+/// Where a `width` x `height` grid is drawn on screen: square cells, with the longer side
+/// filling the view.
+fn view_rect(width: usize, height: usize) -> Rect {
+    let cell = VIEW_SIZE / width.max(height) as f32;
+    Rect::new(OFFSET_X, OFFSET_Y, width as f32 * cell, height as f32 * cell)
+}
+
+/// Size of `field`'s grid and where it's drawn.
+fn grid_and_view(field: &dyn Field) -> (Vec2, Rect) {
+    let shape = field.u().shape().to_vec();
+    (vec2(shape[0] as f32, shape[1] as f32), view_rect(shape[0], shape[1]))
+}
+
+/// A texture of `image` that keeps cells sharp when scaled up.
+fn nearest_texture(image: &Image) -> Texture2D {
+    let texture = Texture2D::from_image(image);
+    texture.set_filter(FilterMode::Nearest);
+    texture
+}
+
+/// Cells along the line from `from` to `to` in a `width` x `height` grid, one per step of at
+/// most a cell in either direction.
+fn section_cells(from: Vec2, to: Vec2, width: usize, height: usize) -> Vec<(usize, usize)> {
+    let steps = (to - from).abs().max_element().ceil().max(1.) as usize;
+    (0..=steps)
+        .map(|i| {
+            let p = from.lerp(to, i as f32 / steps as f32);
+            ((p.x.max(0.) as usize).min(width - 1), (p.y.max(0.) as usize).min(height - 1))
+        })
+        .collect()
+}
+
+/// Label the graph in `rect`: u from -`u_range` to `u_range` on the left in `U_COLOR`, v on
+/// the right in `V_COLOR`, with a tick and grid line at every half range.
+fn draw_axes(rect: Rect, u_range: f32, v_range: f32) {
+    for i in -2..=2 {
+        let y = rect.y + (1. - i as f32 / 2.) / 2. * rect.h;
+        let color = if i == 0 { GRAY } else { DARKGRAY };
+        draw_line(rect.x, y, rect.right(), y, 1., color);
+        draw_line(rect.x - 4., y, rect.x, y, 1., BLACK);
+        draw_line(rect.right(), y, rect.right() + 4., y, 1., BLACK);
+        let u_label = short_number(u_range * i as f32 / 2.);
+        let label_width = measure_text(&u_label, None, GRAPH_FONT_SIZE as u16, 1.).width;
+        let baseline = y + GRAPH_FONT_SIZE / 3.;
+        draw_text(&u_label, rect.x - 6. - label_width, baseline, GRAPH_FONT_SIZE, U_COLOR);
+        draw_text(&short_number(v_range * i as f32 / 2.), rect.right() + 6., baseline, GRAPH_FONT_SIZE, V_COLOR);
+    }
+    draw_text("u", rect.x + 4., rect.y + GRAPH_FONT_SIZE, GRAPH_FONT_SIZE, U_COLOR);
+    draw_text("v", rect.right() - 12., rect.y + GRAPH_FONT_SIZE, GRAPH_FONT_SIZE, V_COLOR);
+}
+
+/// Part of the segment from `a` to `b` inside `rect`, if any.
+fn clip_segment(a: Vec2, b: Vec2, rect: Rect) -> Option<(Vec2, Vec2)> {
+    let (mut t0, mut t1) = (0f32, 1f32);
+    let d = b - a;
+    for (p, q) in [
+        (-d.x, a.x - rect.x),
+        (d.x, rect.right() - a.x),
+        (-d.y, a.y - rect.y),
+        (d.y, rect.bottom() - a.y),
+    ] {
+        if p == 0. {
+            if q < 0. {
+                return None;
+            }
+        } else if p < 0. {
+            t0 = t0.max(q / p);
+        } else {
+            t1 = t1.min(q / p);
+        }
+    }
+    (t0 <= t1).then(|| (a + d * t0, a + d * t1))
+}
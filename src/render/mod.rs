@@ -0,0 +1,93 @@
+mod camera;
+mod field1d;
+mod field2d;
+mod record;
+
+pub use field1d::Field1DRenderer;
+pub use field2d::Field2DRenderer;
+pub use record::Recorder;
+
+use camera::Camera;
+
+use macroquad::prelude::*;
+
+use crate::colormap::{ColorScale, Colormap};
+use crate::field::Field;
+
+/// colors telling probes apart, on the field and in their charts
+const PROBE_COLORS: [Color; 8] = [MAGENTA, ORANGE, BLUE, DARKGREEN, PURPLE, BROWN, GOLD, DARKBLUE];
+
+/// width of the color bar next to the field
+const LEGEND_WIDTH: f32 = 12.0;
+const LEGEND_FONT_SIZE: f32 = 16.0;
+
+/// Draws a field's state with macroquad. Kept apart from the fields themselves so the
+/// simulation runs without a window.
+pub trait FieldRenderer {
+    fn render(&mut self, field: &dyn Field, colors: ColorScale);
+    /// Map a screen position onto the grid, returning `None` when it falls outside the field.
+    fn screen_to_cell(&self, field: &dyn Field, pos: Vec2) -> Option<Vec2>;
+    /// Screen position of a point on the grid, `None` when it's out of view.
+    fn cell_to_screen(&self, field: &dyn Field, cell: Vec2) -> Option<Vec2>;
+    /// Zoom in by `factor`, or out for factors below 1, about the screen position `pos`.
+    fn zoom(&mut self, field: &dyn Field, pos: Vec2, factor: f32);
+    /// Move the view along with the mouse dragged by `delta` on screen.
+    fn pan(&mut self, field: &dyn Field, delta: Vec2);
+    /// Show the whole field again.
+    fn fit(&mut self);
+    /// Where `field` is drawn on screen.
+    fn view(&self, field: &dyn Field) -> Rect;
+    /// Graph u and v along the line from the first to the second point, in cell coordinates,
+    /// or along the default line for `None`. Renderers without such a graph ignore it.
+    fn set_section(&mut self, _section: Option<(Vec2, Vec2)>) {}
+    /// Show or hide the magnitude of the spatial Fourier transform of u, where available.
+    fn toggle_spectrum(&mut self) -> Result<(), String> {
+        Err("the spatial spectrum is only shown for 2D fields".to_string())
+    }
+    /// Images drawn in the last frame at grid resolution, by name, for recording.
+    fn frame_images(&self) -> Vec<(&'static str, &Image)>;
+}
+
+/// Draw a color bar for `colors` with its top left corner at `(x, y)`, the largest value at the
+/// top, labeled above and below.
+fn draw_legend(colors: ColorScale, x: f32, y: f32, height: f32) {
+    let rows = height as u32;
+    for row in 0..rows {
+        let t = 1. - 2. * (row as f32 + 0.5) / rows as f32;
+        let [red, green, blue] = colors.colormap.legend(t);
+        draw_rectangle(x, y + row as f32, LEGEND_WIDTH, 1., Color::from_rgba(red, green, blue, 255));
+    }
+    draw_rectangle_lines(x, y, LEGEND_WIDTH, height, 1., BLACK);
+    let (top, bottom) = match colors.colormap {
+        Colormap::Phase => ("π".to_string(), "-π".to_string()),
+        _ => (format!("+{}", short_number(colors.range.u)), format!("-{}", short_number(colors.range.u))),
+    };
+    draw_text(&top, x, y - 4., LEGEND_FONT_SIZE, BLACK);
+    draw_text(&bottom, x, y + height + LEGEND_FONT_SIZE - 2., LEGEND_FONT_SIZE, BLACK);
+}
+
+/// `value` in at most about four characters plus its sign, for labels.
+pub fn short_number(value: f32) -> String {
+    let magnitude = value.abs();
+    if value == 0. {
+        "0".to_string()
+    } else if !(1e-2..1e4).contains(&magnitude) {
+        format!("{value:.0e}")
+    } else if magnitude >= 100. {
+        format!("{value:.0}")
+    } else {
+        format!("{value:.2}").trim_end_matches('0').trim_end_matches('.').to_string()
+    }
+}
+
+/// Smallest of 1, 2 or 5 times a power of ten that's at least `max`.
+pub fn nice_range(max: f32) -> f32 {
+    let max = max.max(1e-3);
+    let power = 10f32.powf(max.log10().floor());
+    [1., 2., 5., 10.].into_iter().map(|m| m * power).find(|&range| range >= max).unwrap_or(10. * power)
+}
+
+/// Color of the `i`th probe.
+pub fn probe_color(i: usize) -> Color {
+    PROBE_COLORS[i % PROBE_COLORS.len()]
+}
@@ -0,0 +1,69 @@
+use std::path::{Path, PathBuf};
+
+use image::imageops::{self, FilterType};
+use image::RgbaImage;
+use macroquad::prelude::*;
+
+use super::FieldRenderer;
+
+/// directory each recording gets its own numbered subdirectory in
+const RECORDING_DIR: &str = "recordings";
+
+/// Writes the images of every `every`th rendered frame the field changed in to numbered PNGs,
+/// e.g. `recordings/003/field_00012.png`.
+pub struct Recorder {
+    pub dir: PathBuf,
+    /// record one in this many frames the field changed in
+    pub every: u32,
+    /// integer upscaling factor, 1 for one pixel per cell
+    pub scale: u32,
+    /// frames offered to `capture` since recording started
+    rendered: u32,
+    /// frames written so far
+    pub written: u32,
+}
+
+impl Recorder {
+    /// Start a recording in the first unused subdirectory of `RECORDING_DIR`.
+    pub fn start(every: u32, scale: u32) -> Result<Recorder, String> {
+        let dir = (0..)
+            .map(|n| Path::new(RECORDING_DIR).join(format!("{n:03}")))
+            .find(|dir| !dir.exists())
+            .unwrap();
+        std::fs::create_dir_all(&dir).map_err(|err| format!("{}: {err}", dir.display()))?;
+        Ok(Recorder {
+            dir,
+            every: every.max(1),
+            scale: scale.max(1),
+            rendered: 0,
+            written: 0,
+        })
+    }
+
+    /// Call once per rendered frame the field changed in, after `renderer` has drawn it.
+    pub fn capture(&mut self, renderer: &dyn FieldRenderer) -> Result<(), String> {
+        self.rendered += 1;
+        if !(self.rendered - 1).is_multiple_of(self.every) {
+            return Ok(());
+        }
+        let images = renderer.frame_images();
+        if images.is_empty() {
+            return Err("nothing to record for this kind of field".to_string());
+        }
+        for (name, image) in images {
+            let path = self.dir.join(format!("{name}_{:05}.png", self.written));
+            self.save(image, &path)?;
+        }
+        self.written += 1;
+        Ok(())
+    }
+
+    fn save(&self, image: &Image, path: &Path) -> Result<(), String> {
+        let (width, height) = (image.width as u32, image.height as u32);
+        let mut buffer = RgbaImage::from_raw(width, height, image.bytes.clone()).unwrap();
+        if self.scale > 1 {
+            buffer = imageops::resize(&buffer, width * self.scale, height * self.scale, FilterType::Nearest);
+        }
+        buffer.save(path).map_err(|err| format!("{}: {err}", path.display()))
+    }
+}
@@ -0,0 +1,206 @@
+use std::collections::VecDeque;
+
+use macroquad::prelude::*;
+use ndarray::ArrayD;
+
+use crate::field::{Field, Source};
+
+/// memory the saved states may take up together
+const BUDGET_BYTES: usize = 256 << 20;
+/// most states kept, however small the field
+const MAX_FRAMES: usize = 1024;
+
+/// gap between the field and the timeline slider below it
+const SLIDER_GAP: f32 = 12.0;
+const SLIDER_WIDTH: f32 = 400.0;
+const SLIDER_HEIGHT: f32 = 16.0;
+
+/// The field as it was after one rendered frame's updates.
+struct Frame {
+    u: ArrayD<f32>,
+    v: ArrayD<f32>,
+    step: u32,
+    sources: Vec<Source>,
+}
+
+/// Bounded history of the field's states, oldest first, to step and scrub back through. Once it
+/// is full the oldest states are dropped.
+pub struct Rewind {
+    frames: VecDeque<Frame>,
+    /// frame the field is currently at; anything after it was rewound past
+    position: usize,
+}
+
+impl Rewind {
+    pub fn new() -> Self {
+        Rewind {
+            frames: VecDeque::new(),
+            position: 0,
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.frames.clear();
+        self.position = 0;
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.frames.is_empty()
+    }
+
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    /// Whether the field is at an earlier state than the latest one kept.
+    pub fn rewound(&self) -> bool {
+        self.position + 1 < self.frames.len()
+    }
+
+    /// Save the state of `field`. The states after the current one are dropped, since the field
+    /// has moved on from there in a new direction.
+    pub fn record(&mut self, field: &dyn Field) {
+        self.frames.truncate(self.position + 1);
+        while self.frames.len() >= capacity(field.u().len()) {
+            self.frames.pop_front();
+        }
+        self.frames.push_back(Frame {
+            u: field.u().to_owned(),
+            v: field.v().to_owned(),
+            step: field.step(),
+            sources: field.sources().to_vec(),
+        });
+        self.position = self.frames.len() - 1;
+    }
+
+    /// Replace the current state with `field`'s after an edit. The states after it came from the
+    /// unedited one, so they're dropped.
+    pub fn branch(&mut self, field: &dyn Field) {
+        self.frames.truncate(self.position);
+        self.record(field);
+    }
+
+    /// Drop the states after `step`, which running time backwards has gone back past, and move to
+    /// the latest one left.
+    pub fn truncate_after(&mut self, step: u32) {
+        while self.frames.back().is_some_and(|frame| frame.step > step) {
+            self.frames.pop_back();
+        }
+        self.position = self.frames.len().saturating_sub(1);
+    }
+
+    /// Put `field` back into the state at `position`, returning the step it was saved at.
+    pub fn seek(&mut self, field: &mut dyn Field, position: usize) -> Result<u32, String> {
+        let frame = self.frames.get(position).ok_or("no such state in the history")?;
+        field
+            .restore_state(frame.u.clone(), frame.v.clone(), frame.step)
+            .map_err(|err| format!("can't rewind to a field of another shape: {err}"))?;
+        field.clear_sources();
+        for &source in &frame.sources {
+            field.add_source(source);
+        }
+        self.position = position;
+        Ok(frame.step)
+    }
+
+    /// History position under the screen position `pos`, if it's on the timeline slider below the
+    /// field drawn at `view`. With `dragging` the slider's whole width counts, however far above or
+    /// below `pos` is.
+    pub fn position_at(&self, view: Rect, pos: Vec2, dragging: bool) -> Option<usize> {
+        let slider = slider(view);
+        if self.frames.is_empty() || !(dragging || slider.contains(pos)) {
+            return None;
+        }
+        let rel = ((pos.x - slider.x) / slider.w).clamp(0., 1.);
+        Some((rel * (self.frames.len() - 1) as f32).round() as usize)
+    }
+
+    /// Draw the timeline below the field drawn at `view`, the kept range of steps with a handle at
+    /// the current state and its step below.
+    pub fn draw(&self, view: Rect) {
+        let Rect { x: slider_x, y: slider_y, .. } = slider(view);
+        draw_rectangle(slider_x, slider_y + SLIDER_HEIGHT / 2. - 2., SLIDER_WIDTH, 4., LIGHTGRAY);
+        let (Some(first), Some(last)) = (self.frames.front(), self.frames.back()) else {
+            return;
+        };
+        let rel = if self.frames.len() > 1 {
+            self.position as f32 / (self.frames.len() - 1) as f32
+        } else {
+            1.
+        };
+        draw_rectangle(slider_x, slider_y + SLIDER_HEIGHT / 2. - 2., SLIDER_WIDTH * rel, 4., DARKGRAY);
+        draw_rectangle(slider_x + SLIDER_WIDTH * rel - 3., slider_y, 6., SLIDER_HEIGHT, BLACK);
+        let current = &self.frames[self.position];
+        let label = format!("step {} ({}..{} kept)", current.step, first.step, last.step);
+        draw_text(&label, slider_x, slider_y + SLIDER_HEIGHT + 14., 16., BLACK);
+    }
+}
+
+/// Number of states of a field of `cells` cells kept at once, each taking 8 bytes per cell for u
+/// and v.
+fn capacity(cells: usize) -> usize {
+    (BUDGET_BYTES / (cells * 8).max(1)).clamp(2, MAX_FRAMES)
+}
+
+/// Where the timeline slider goes below the field drawn at `view`.
+fn slider(view: Rect) -> Rect {
+    Rect::new(view.x, view.bottom() + SLIDER_GAP, SLIDER_WIDTH, SLIDER_HEIGHT)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::field::{Disturbance, Field1D, Field1DInit, FieldConfig};
+
+    /// A history of a small field saved at steps `0..count`.
+    fn recorded(field: &mut Field1D, count: u32) -> Rewind {
+        let mut rewind = Rewind::new();
+        for step in 0..count {
+            field.set_step(step);
+            rewind.record(field);
+        }
+        rewind
+    }
+
+    fn steps(rewind: &Rewind) -> Vec<u32> {
+        rewind.frames.iter().map(|frame| frame.step).collect()
+    }
+
+    #[test]
+    fn branch_drops_the_later_states() {
+        let mut field = Field1D::new(Field1DInit::Centered, 16, FieldConfig::default());
+        let mut rewind = recorded(&mut field, 6);
+        assert_eq!(rewind.seek(&mut field, 2), Ok(2));
+        field.add_disturbance((3., 0.), Disturbance::default());
+        rewind.branch(&field);
+        assert_eq!(steps(&rewind), [0, 1, 2]);
+        assert_eq!(rewind.position(), 2);
+        assert!(!rewind.rewound());
+        assert_eq!(rewind.frames[2].u, field.u());
+    }
+
+    #[test]
+    fn truncate_after_moves_to_the_last_state_kept() {
+        let mut field = Field1D::new(Field1DInit::Centered, 16, FieldConfig::default());
+        let mut rewind = recorded(&mut field, 6);
+        rewind.truncate_after(2);
+        assert_eq!(steps(&rewind), [0, 1, 2]);
+        assert_eq!(rewind.position(), 2);
+    }
+
+    #[test]
+    fn full_history_drops_the_oldest_state() {
+        let mut field = Field1D::new(Field1DInit::Centered, 16, FieldConfig::default());
+        let rewind = recorded(&mut field, MAX_FRAMES as u32 + 1);
+        assert_eq!(rewind.frames.len(), MAX_FRAMES);
+        assert_eq!(rewind.frames.front().map(|frame| frame.step), Some(1));
+        assert_eq!(rewind.position(), MAX_FRAMES - 1);
+    }
+
+    #[test]
+    fn capacity_stays_within_the_budget() {
+        assert_eq!(capacity(16), MAX_FRAMES);
+        assert_eq!(capacity(BUDGET_BYTES / 8 / 10), 10);
+        assert_eq!(capacity(BUDGET_BYTES), 2);
+    }
+}
@@ -0,0 +1,448 @@
+//! Scenario files: a whole setup (grid, speed map, boundary, initial state, obstacles, sources
+//! and view settings) described in TOML or JSON instead of code.
+//!
+//! ```toml
+//! name = "lens"
+//! dims = 2
+//! width = 160
+//! height = 96
+//!
+//! [config]
+//! boundary = "absorbing"
+//! integrator = "verlet"
+//!
+//! [[index]]
+//! index = 1.5
+//! region = { shape = "circle", center = [80, 48], radius = 24 }
+//!
+//! [[initial]]
+//! kind = "packet"
+//! from = 4
+//! to = 40
+//! wavelength = 8
+//!
+//! [[obstacles]]
+//! shape = "rect"
+//! from = [120, 0]
+//! to = [122, 40]
+//!
+//! [[sources]]
+//! at = [10, 48]
+//! frequency = 0.02
+//!
+//! [view]
+//! updates_per_frame = 8
+//! ```
+//!
+//! Coordinates are in cells. 1D scenarios use the same format and ignore y.
+
+use std::f32::consts::PI;
+use std::path::{Path, PathBuf};
+
+use ndarray::{Array2, ArrayD, Axis, Dimension, IxDyn};
+use serde::{Deserialize, Deserializer};
+
+use crate::field::{
+    Boundary, Disturbance, Field, Field1D, Field1DInit, Field2D, Field2DInit, FieldConfig, Integrator, Medium,
+    Source, SourceShape, Waveform, DEFAULT_FREQUENCY, PULSE_DURATION,
+};
+use crate::label::{from_label, Labeled};
+
+fn labeled<'de, D: Deserializer<'de>, T: Labeled>(deserializer: D) -> Result<T, D::Error> {
+    let name = String::deserialize(deserializer)?;
+    from_label(&name).map_err(serde::de::Error::custom)
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Scenario {
+    /// shown in the app, defaults to the file name
+    #[serde(default)]
+    pub name: String,
+    #[serde(default = "default_dims")]
+    pub dims: usize,
+    /// defaults to the size of `init`
+    pub width: Option<usize>,
+    pub height: Option<usize>,
+    /// preset the initial state and obstacles start from, before the rest of the file is added
+    pub init: Option<String>,
+    #[serde(default)]
+    pub config: ConfigSpec,
+    /// refractive index regions on top of the medium preset, later ones win where they overlap
+    #[serde(default)]
+    pub index: Vec<IndexSpec>,
+    #[serde(default)]
+    pub initial: Vec<InitialSpec>,
+    #[serde(default)]
+    pub obstacles: Vec<Region>,
+    #[serde(default)]
+    pub sources: Vec<SourceSpec>,
+    #[serde(default)]
+    pub view: ViewSpec,
+    /// file the scenario was loaded from
+    #[serde(skip)]
+    pub path: PathBuf,
+}
+
+fn default_dims() -> usize {
+    2
+}
+
+/// Starting values of the settings the app can also change at runtime.
+#[derive(Copy, Clone, Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ConfigSpec {
+    #[serde(deserialize_with = "labeled")]
+    pub boundary: Boundary,
+    #[serde(deserialize_with = "labeled")]
+    pub medium: Medium,
+    pub damping: f32,
+    #[serde(deserialize_with = "labeled")]
+    pub integrator: Integrator,
+    pub dt: f32,
+}
+
+impl Default for ConfigSpec {
+    fn default() -> Self {
+        let config = FieldConfig::default();
+        ConfigSpec {
+            boundary: config.boundary,
+            medium: config.medium,
+            damping: config.damping,
+            integrator: config.integrator,
+            dt: config.dt,
+        }
+    }
+}
+
+impl From<ConfigSpec> for FieldConfig {
+    fn from(spec: ConfigSpec) -> Self {
+        FieldConfig {
+            boundary: spec.boundary,
+            medium: spec.medium,
+            damping: spec.damping,
+            integrator: spec.integrator,
+            dt: spec.dt,
+        }
+    }
+}
+
+/// Cells whose centers fall inside a shape.
+#[derive(Copy, Clone, Debug, Deserialize)]
+#[serde(tag = "shape", rename_all = "lowercase", deny_unknown_fields)]
+pub enum Region {
+    /// `from` inclusive, `to` exclusive
+    Rect { from: [f32; 2], to: [f32; 2] },
+    Circle { center: [f32; 2], radius: f32 },
+}
+
+impl Region {
+    fn contains(&self, x: usize, y: usize) -> bool {
+        let (x, y) = (x as f32 + 0.5, y as f32 + 0.5);
+        match *self {
+            Region::Rect { from, to } => (from[0]..to[0]).contains(&x) && (from[1]..to[1]).contains(&y),
+            Region::Circle { center, radius } => (x - center[0]).hypot(y - center[1]) < radius,
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct IndexSpec {
+    pub index: f32,
+    pub region: Region,
+}
+
+/// Part of the initial state, added on top of the preset's.
+#[derive(Copy, Clone, Debug, Deserialize)]
+#[serde(tag = "kind", rename_all = "lowercase", deny_unknown_fields)]
+pub enum InitialSpec {
+    /// cosine bump in `u`, at rest
+    Bump {
+        center: [f32; 2],
+        radius: f32,
+        #[serde(default = "default_amplitude")]
+        amplitude: f32,
+    },
+    /// plane wave under a smooth envelope spanning `from..to` along x, travelling towards +x,
+    /// or -x if `reverse`
+    Packet {
+        from: f32,
+        to: f32,
+        wavelength: f32,
+        #[serde(default = "default_amplitude")]
+        amplitude: f32,
+        #[serde(default)]
+        reverse: bool,
+    },
+}
+
+fn default_amplitude() -> f32 {
+    255.
+}
+
+#[derive(Copy, Clone, Debug, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum WaveformSpec {
+    #[default]
+    Sine,
+    Pulse,
+}
+
+/// A source driving a point, or a line if `to` is given.
+#[derive(Copy, Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SourceSpec {
+    pub at: [usize; 2],
+    pub to: Option<[usize; 2]>,
+    #[serde(default)]
+    pub waveform: WaveformSpec,
+    /// cycles per step, defaults to the app's for sines and 0 for pulses
+    pub frequency: Option<f32>,
+    #[serde(default = "default_amplitude")]
+    pub amplitude: f32,
+    #[serde(default)]
+    pub phase: f32,
+    #[serde(default)]
+    pub start: u32,
+    /// pulses default to switching off after the app's pulse duration
+    pub stop: Option<u32>,
+}
+
+impl SourceSpec {
+    fn source(&self) -> Source {
+        let shape = match self.to {
+            Some(to) => SourceShape::Line(self.at.into(), to.into()),
+            None => SourceShape::Point(self.at.into()),
+        };
+        let (waveform, frequency, stop) = match self.waveform {
+            WaveformSpec::Sine => (Waveform::Sine, DEFAULT_FREQUENCY, None),
+            WaveformSpec::Pulse => (Waveform::GaussianPulse, 0., Some(self.start + PULSE_DURATION)),
+        };
+        Source {
+            shape,
+            waveform,
+            frequency: self.frequency.unwrap_or(frequency),
+            amplitude: self.amplitude,
+            phase: self.phase,
+            start: self.start,
+            stop: self.stop.or(stop),
+        }
+    }
+}
+
+/// How the app shows the scenario when it's loaded.
+#[derive(Copy, Clone, Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ViewSpec {
+    pub updates_per_frame: u32,
+    /// start running rather than paused
+    pub running: bool,
+}
+
+impl Default for ViewSpec {
+    fn default() -> Self {
+        ViewSpec {
+            updates_per_frame: 4,
+            running: false,
+        }
+    }
+}
+
+impl Scenario {
+    /// Read a scenario from a `.toml` or `.json` file.
+    pub fn load(path: &Path) -> Result<Scenario, String> {
+        let text = std::fs::read_to_string(path).map_err(|err| format!("{}: {err}", path.display()))?;
+        let parsed = match path.extension().and_then(|ext| ext.to_str()) {
+            Some("json") => serde_json::from_str(&text).map_err(|err| err.to_string()),
+            _ => toml::from_str(&text).map_err(|err| err.to_string()),
+        };
+        let mut scenario: Scenario = parsed.map_err(|err| format!("{}: {err}", path.display()))?;
+        if scenario.dims != 1 && scenario.dims != 2 {
+            return Err(format!("{}: dims must be 1 or 2, got {}", path.display(), scenario.dims));
+        }
+        if scenario.name.is_empty() {
+            scenario.name = path.file_stem().unwrap_or_default().to_string_lossy().into_owned();
+        }
+        scenario.path = path.to_path_buf();
+        // catch bad preset names now rather than on every reset
+        scenario.build(scenario.config.into(), false)?;
+        Ok(scenario)
+    }
+
+    /// Set up a field as described, with `config` in place of the scenario's own settings.
+    pub fn build(&self, config: FieldConfig, vectorized: bool) -> Result<Box<dyn Field>, String> {
+        let mut field = self.build_preset(config, vectorized)?;
+        let (u, v) = (field.u().to_owned(), field.v().to_owned());
+        let (u, v) = self.add_initial(u, v, field.c2().to_owned());
+        field.set_state(u, v).map_err(|err| err.to_string())?;
+        let shape = field.u().shape().to_vec();
+        for source in &self.sources {
+            let source = source.source();
+            if !source.fits(&shape) {
+                return Err(format!("source {} reaches outside the {shape:?} grid", source.label()));
+            }
+            field.add_source(source);
+        }
+        Ok(field)
+    }
+
+    /// The field with the preset's state and obstacles and the scenario's size, index and obstacles.
+    fn build_preset(&self, config: FieldConfig, vectorized: bool) -> Result<Box<dyn Field>, String> {
+        let preset: Box<dyn Field> = if self.dims == 1 {
+            let init = self.init.as_deref().map_or(Ok(Field1DInit::AtEnd), from_label)?;
+            Box::new(Field1D::new(init, self.width.unwrap_or(init.default_width()), config))
+        } else {
+            let init = self.init.as_deref().map_or(Ok(Field2DInit::Zero), from_label)?;
+            let (width, height) = init.default_size();
+            let size = (self.width.unwrap_or(width), self.height.unwrap_or(height));
+            Box::new(Field2D::new(vectorized, init, size, config))
+        };
+        if self.index.is_empty() && self.obstacles.is_empty() {
+            return Ok(preset);
+        }
+
+        let shape = preset.u().shape().to_vec();
+        let (width, height) = (shape[0], shape.get(1).copied().unwrap_or(1));
+        let index = Array2::from_shape_fn((width, height), |(x, y)| {
+            self.index
+                .iter()
+                .rev()
+                .find(|spec| spec.region.contains(x, y))
+                .map_or(1., |spec| spec.index)
+        });
+        let mut field: Box<dyn Field> = if self.dims == 1 {
+            if !self.obstacles.is_empty() {
+                return Err("obstacles need a 2D field".to_string());
+            }
+            Box::new(Field1D::from_index(index.index_axis_move(Axis(1), 0), config))
+        } else {
+            let mut mask = Array2::from_shape_fn((width, height), |(x, y)| {
+                self.obstacles.iter().any(|region| region.contains(x, y))
+            });
+            if let Some(preset_mask) = preset.mask() {
+                mask.zip_mut_with(&preset_mask, |mask, &preset| *mask |= preset);
+            }
+            Box::new(Field2D::from_maps(vectorized, index, mask, config)?)
+        };
+        field
+            .set_state(preset.u().to_owned(), preset.v().to_owned())
+            .map_err(|err| err.to_string())?;
+        for source in preset.sources() {
+            field.add_source(*source);
+        }
+        Ok(field)
+    }
+
+    /// Add the bumps and packets to the state `(u, v)` of a field with the given `c2`.
+    fn add_initial(&self, mut u: ArrayD<f32>, mut v: ArrayD<f32>, c2: ArrayD<f32>) -> (ArrayD<f32>, ArrayD<f32>) {
+        for (cell, u) in u.indexed_iter_mut() {
+            let (x, y) = cell_center(&cell);
+            for initial in &self.initial {
+                if let InitialSpec::Bump { center, radius, amplitude } = *initial {
+                    let distance = (x - center[0]).hypot(y - center[1]);
+                    *u += Disturbance { radius, amplitude }.displacement(distance);
+                }
+            }
+        }
+        for initial in &self.initial {
+            let InitialSpec::Packet { from, to, wavelength, amplitude, reverse } = *initial else {
+                continue;
+            };
+            let packet = |x: f32| {
+                if x < from || x > to {
+                    return 0.;
+                }
+                // smooth envelope so the packet doesn't carry sharp fronts
+                let envelope = ((x - from) / (to - from) * PI).sin().powi(2);
+                amplitude * envelope * (2. * PI * x / wavelength).cos()
+            };
+            let direction = if reverse { -1. } else { 1. };
+            for (cell, u) in u.indexed_iter_mut() {
+                let (x, _) = cell_center(&cell);
+                *u += packet(x);
+                // a wave u(x - ct) has v = -c du/dx
+                v[&cell] -= direction * c2[&cell].sqrt() * (packet(x + 0.5) - packet(x - 0.5));
+            }
+        }
+        (u, v)
+    }
+}
+
+fn cell_center(cell: &IxDyn) -> (f32, f32) {
+    let x = cell[0] as f32 + 0.5;
+    let y = if cell.ndim() > 1 { cell[1] as f32 + 0.5 } else { 0.5 };
+    (x, y)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(text: &str) -> Scenario {
+        toml::from_str(text).unwrap()
+    }
+
+    #[test]
+    fn shipped_scenarios_load_and_build() {
+        let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("scenarios");
+        let mut count = 0;
+        for entry in std::fs::read_dir(dir).unwrap() {
+            let path = entry.unwrap().path();
+            if !path.extension().is_some_and(|ext| ext == "toml" || ext == "json") {
+                continue;
+            }
+            let scenario = Scenario::load(&path).unwrap_or_else(|err| panic!("{err}"));
+            for vectorized in [false, true] {
+                let built = scenario.build(scenario.config.into(), vectorized);
+                assert!(built.is_ok(), "{}: {:?}", path.display(), built.err());
+            }
+            count += 1;
+        }
+        assert!(count > 0, "no scenarios found");
+    }
+
+    #[test]
+    fn packet_and_obstacle_end_up_in_the_field() {
+        let scenario = parse(
+            r#"
+            width = 64
+            height = 16
+
+            [[initial]]
+            kind = "packet"
+            from = 4
+            to = 20
+            wavelength = 8
+
+            [[obstacles]]
+            shape = "rect"
+            from = [40, 0]
+            to = [42, 8]
+            "#,
+        );
+        let field = scenario.build(scenario.config.into(), false).unwrap();
+        let (u, mask) = (field.u(), field.mask().unwrap());
+        for ((x, y), &masked) in mask.indexed_iter().map(|(cell, masked)| ((cell[0], cell[1]), masked)) {
+            assert_eq!(masked, (40..42).contains(&x) && y < 8, "({x}, {y})");
+        }
+        let packet = |x: usize| (0..16).map(|y| u[[x, y]].abs()).fold(0f32, f32::max);
+        assert!(packet(12) > 100., "the packet's middle is missing");
+        assert!((0..4).chain(20..64).all(|x| packet(x) == 0.), "the packet spills out of its span");
+    }
+
+    #[test]
+    fn off_grid_sources_are_rejected() {
+        let scenario = parse(
+            r#"
+            width = 16
+            height = 16
+
+            [[sources]]
+            at = [2, 2]
+            to = [2, 16]
+            "#,
+        );
+        assert!(scenario.build(scenario.config.into(), false).is_err());
+    }
+}
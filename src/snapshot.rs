@@ -0,0 +1,271 @@
+//! Full simulation state saved as a NumPy `.npz` archive, for resuming a run later or picking
+//! it up in Python:
+//!
+//! ```python
+//! s = np.load("snapshot.npz")
+//! u, v, step = s["u"], s["v"], int(s["step"])
+//! boundary = bytes(s["boundary"]).decode()
+//! ```
+//!
+//! Arrays are indexed `[x]` or `[x, y]`. Besides `u` and `v` the archive holds `index` and `mask`
+//! (2D only), `step`, `dt`, `damping`, `vectorized`, the `boundary`, `medium` and `integrator`
+//! labels as UTF-8 bytes, and `sources` with one row per source, see `SOURCE_COLUMNS`.
+
+use std::fs::File;
+use std::path::Path;
+
+use ndarray::{arr0, Array0, Array1, Array2, ArrayD, Axis, Ix1, Ix2};
+use ndarray_npy::{NpzReader, NpzWriter};
+
+use crate::field::{Field, Field1D, Field2D, FieldConfig, Source, SourceShape, Waveform};
+use crate::label::from_label;
+
+/// columns of the `sources` table; `line` is 0 for point sources, `pulse` 0 for sines and
+/// `stop` -1 for sources that keep running
+pub const SOURCE_COLUMNS: [&str; 11] =
+    ["line", "x0", "y0", "x1", "y1", "pulse", "frequency", "amplitude", "phase", "start", "stop"];
+
+/// Everything needed to carry on a run where it left off.
+#[derive(Clone, Debug)]
+pub struct Snapshot {
+    pub u: ArrayD<f32>,
+    pub v: ArrayD<f32>,
+    pub index: ArrayD<f32>,
+    /// obstacle cells, only for 2D fields
+    pub mask: Option<ArrayD<bool>>,
+    pub step: u32,
+    pub config: FieldConfig,
+    pub vectorized: bool,
+    pub sources: Vec<Source>,
+}
+
+impl Snapshot {
+    /// Copy the state of `field`, which runs with `config` and on the `vectorized` path.
+    pub fn capture(field: &dyn Field, config: FieldConfig, vectorized: bool) -> Snapshot {
+        Snapshot {
+            u: field.u().to_owned(),
+            v: field.v().to_owned(),
+            index: field.index().to_owned(),
+            mask: field.mask().map(|mask| mask.to_owned()),
+            step: field.step(),
+            config,
+            vectorized,
+            sources: field.sources().to_vec(),
+        }
+    }
+
+    /// A field in the captured state, running with `config` and, in 2D, on the `vectorized` path.
+    pub fn restore(&self, config: FieldConfig, vectorized: bool) -> Result<Box<dyn Field>, String> {
+        let shape_error = |err: ndarray::ShapeError| err.to_string();
+        let mut field: Box<dyn Field> = match &self.mask {
+            None => {
+                let index = self.index.clone().into_dimensionality::<Ix1>().map_err(shape_error)?;
+                Box::new(Field1D::from_index(index, config))
+            }
+            Some(mask) => {
+                let index = self.index.clone().into_dimensionality::<Ix2>().map_err(shape_error)?;
+                let mask = mask.clone().into_dimensionality::<Ix2>().map_err(shape_error)?;
+                Box::new(Field2D::from_maps(vectorized, index, mask, config)?)
+            }
+        };
+        field
+            .set_state(self.u.clone(), self.v.clone())
+            .map_err(|err| format!("u and v don't match the index: {err}"))?;
+        field.set_step(self.step);
+        let shape = field.u().shape().to_vec();
+        for &source in &self.sources {
+            if !source.fits(&shape) {
+                return Err(format!("source {} reaches outside the {shape:?} grid", source.label()));
+            }
+            field.add_source(source);
+        }
+        Ok(field)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        let error = |err: &dyn std::fmt::Display| format!("{}: {err}", path.display());
+        let file = File::create(path).map_err(|err| error(&err))?;
+        let mut npz = NpzWriter::new_compressed(file);
+        let mut write = || -> Result<(), ndarray_npy::WriteNpzError> {
+            npz.add_array("u", &self.u)?;
+            npz.add_array("v", &self.v)?;
+            npz.add_array("index", &self.index)?;
+            if let Some(mask) = &self.mask {
+                npz.add_array("mask", mask)?;
+            }
+            npz.add_array("step", &arr0(self.step))?;
+            npz.add_array("dt", &arr0(self.config.dt))?;
+            npz.add_array("damping", &arr0(self.config.damping))?;
+            npz.add_array("vectorized", &arr0(self.vectorized))?;
+            npz.add_array("boundary", &label_bytes(self.config.boundary.label()))?;
+            npz.add_array("medium", &label_bytes(self.config.medium.label()))?;
+            npz.add_array("integrator", &label_bytes(self.config.integrator.label()))?;
+            npz.add_array("sources", &sources_table(&self.sources))?;
+            Ok(())
+        };
+        write().map_err(|err| error(&err))?;
+        npz.finish().map_err(|err| error(&err))?;
+        Ok(())
+    }
+
+    pub fn load(path: &Path) -> Result<Snapshot, String> {
+        let error = |err: &dyn std::fmt::Display| format!("{}: {err}", path.display());
+        let file = File::open(path).map_err(|err| error(&err))?;
+        let mut npz = NpzReader::new(file).map_err(|err| error(&err))?;
+        let has_mask = npz.names().map_err(|err| error(&err))?.iter().any(|name| name == "mask");
+        let mut read = || -> Result<Snapshot, Box<dyn std::error::Error>> {
+            let label = |npz: &mut NpzReader<File>, name: &str| -> Result<String, Box<dyn std::error::Error>> {
+                let bytes: Array1<u8> = npz.by_name(name)?;
+                Ok(String::from_utf8(bytes.to_vec())?)
+            };
+            let config = FieldConfig {
+                boundary: from_label(&label(&mut npz, "boundary")?)?,
+                medium: from_label(&label(&mut npz, "medium")?)?,
+                damping: npz.by_name("damping").map(|a: Array0<f32>| a.into_scalar())?,
+                integrator: from_label(&label(&mut npz, "integrator")?)?,
+                dt: npz.by_name("dt").map(|a: Array0<f32>| a.into_scalar())?,
+            };
+            Ok(Snapshot {
+                u: npz.by_name("u")?,
+                v: npz.by_name("v")?,
+                index: npz.by_name("index")?,
+                mask: if has_mask { Some(npz.by_name("mask")?) } else { None },
+                step: npz.by_name("step").map(|a: Array0<u32>| a.into_scalar())?,
+                config,
+                vectorized: npz.by_name("vectorized").map(|a: Array0<bool>| a.into_scalar())?,
+                sources: sources_from_table(&npz.by_name("sources")?)?,
+            })
+        };
+        read().map_err(|err| error(&err))
+    }
+}
+
+fn label_bytes(label: &str) -> Array1<u8> {
+    Array1::from(label.as_bytes().to_vec())
+}
+
+fn sources_table(sources: &[Source]) -> Array2<f64> {
+    let mut table = Array2::zeros((0, SOURCE_COLUMNS.len()));
+    for source in sources {
+        let (line, from, to) = match source.shape {
+            SourceShape::Point(at) => (0., at, at),
+            SourceShape::Line(from, to) => (1., from, to),
+        };
+        let pulse = match source.waveform {
+            Waveform::Sine => 0.,
+            Waveform::GaussianPulse => 1.,
+        };
+        let row = [
+            line,
+            from.0 as f64,
+            from.1 as f64,
+            to.0 as f64,
+            to.1 as f64,
+            pulse,
+            source.frequency as f64,
+            source.amplitude as f64,
+            source.phase as f64,
+            source.start as f64,
+            source.stop.map_or(-1., |stop| stop as f64),
+        ];
+        table.push_row(row[..].into()).unwrap();
+    }
+    table
+}
+
+fn sources_from_table(table: &Array2<f64>) -> Result<Vec<Source>, String> {
+    if table.ncols() != SOURCE_COLUMNS.len() {
+        return Err(format!("sources has {} columns, expected {}", table.ncols(), SOURCE_COLUMNS.len()));
+    }
+    let sources = table
+        .axis_iter(Axis(0))
+        .map(|row| {
+            let from = (row[1] as usize, row[2] as usize);
+            let to = (row[3] as usize, row[4] as usize);
+            Source {
+                shape: if row[0] == 0. { SourceShape::Point(from) } else { SourceShape::Line(from, to) },
+                waveform: if row[5] == 0. { Waveform::Sine } else { Waveform::GaussianPulse },
+                frequency: row[6] as f32,
+                amplitude: row[7] as f32,
+                phase: row[8] as f32,
+                start: row[9] as u32,
+                stop: (row[10] >= 0.).then_some(row[10] as u32),
+            }
+        })
+        .collect();
+    Ok(sources)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::field::{Boundary, Field1DInit, Field2DInit, Integrator, Medium, SourceKind};
+
+    fn config() -> FieldConfig {
+        FieldConfig {
+            boundary: Boundary::Absorbing,
+            medium: Medium::GlassSlab,
+            damping: 0.002,
+            integrator: Integrator::Verlet,
+            dt: 0.5,
+        }
+    }
+
+    /// Save a snapshot of `field` and load it back.
+    fn round_trip(field: &dyn Field, name: &str) -> Snapshot {
+        let path = std::env::temp_dir().join(format!("pixel_wave_snapshot_{}_{name}.npz", std::process::id()));
+        let saved = Snapshot::capture(field, config(), false);
+        saved.save(&path).unwrap();
+        let loaded = Snapshot::load(&path);
+        std::fs::remove_file(&path).unwrap();
+        loaded.unwrap()
+    }
+
+    fn assert_restores(field: &dyn Field, name: &str) {
+        let snapshot = round_trip(field, name);
+        assert_eq!(snapshot.u, field.u());
+        assert_eq!(snapshot.v, field.v());
+        assert_eq!(snapshot.index, field.index());
+        assert_eq!(snapshot.mask.as_ref().map(|mask| mask.view()), field.mask());
+        assert_eq!(snapshot.step, field.step());
+        assert_eq!(snapshot.config, config());
+        assert_eq!(sources_table(&snapshot.sources), sources_table(field.sources()));
+
+        let restored = snapshot.restore(snapshot.config, snapshot.vectorized).unwrap();
+        assert_eq!(restored.u(), field.u());
+        assert_eq!(restored.v(), field.v());
+        assert_eq!(restored.index(), field.index());
+        assert_eq!(restored.mask(), field.mask());
+        assert_eq!(restored.step(), field.step());
+        assert_eq!(sources_table(restored.sources()), sources_table(field.sources()));
+    }
+
+    #[test]
+    fn field_1d_round_trips() {
+        let mut field = Field1D::new(Field1DInit::Centered, 64, config());
+        field.add_source(SourceKind::Pulse.place((10, 0), (10, 0), 100., 3));
+        for _ in 0..20 {
+            field.update();
+        }
+        assert_restores(&field, "1d");
+    }
+
+    #[test]
+    fn masked_field_2d_round_trips() {
+        let mut field = Field2D::new(false, Field2DInit::DoubleSlit, (32, 24), config());
+        field.add_source(SourceKind::Line.place((20, 2), (28, 20), 50., 5));
+        for _ in 0..20 {
+            field.update();
+        }
+        assert!(field.mask().unwrap().iter().any(|&masked| masked));
+        assert_restores(&field, "2d");
+    }
+
+    #[test]
+    fn off_grid_sources_are_rejected() {
+        let field = Field2D::new(false, Field2DInit::Zero, (8, 8), config());
+        let mut snapshot = Snapshot::capture(&field, config(), false);
+        snapshot.sources.push(SourceKind::Line.place((1, 1), (1_000_000_000, 1), 1., 0));
+        assert!(snapshot.restore(config(), false).is_err());
+    }
+}
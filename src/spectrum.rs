@@ -0,0 +1,135 @@
+//! Power spectra of time series such as a probe's u(t), with frequencies in cycles per step,
+//! and spatial spectra of 2D fields.
+
+use ndarray::{Array2, ArrayView2, Axis};
+use rustfft::num_complex::Complex;
+use rustfft::FftPlanner;
+
+/// A spectral peak, located between bins by fitting a parabola through its neighbours.
+#[derive(Copy, Clone, Debug)]
+pub struct Peak {
+    /// cycles per step
+    pub frequency: f32,
+    pub power: f32,
+}
+
+/// One-sided power spectrum of a real signal sampled once per step.
+pub struct Spectrum {
+    /// power at `i / len` cycles per step, from 0 up to the Nyquist frequency
+    pub power: Vec<f32>,
+    /// number of samples analysed
+    pub len: usize,
+}
+
+impl Spectrum {
+    /// Spectrum of the latest samples of `signal`, as many as the largest power of two that
+    /// fits. The mean is removed and a Hann window applied, so a constant offset or the cut at
+    /// either end doesn't leak across the spectrum. `None` for fewer than 8 samples.
+    pub fn of(signal: &[f32]) -> Option<Spectrum> {
+        if signal.len() < 8 {
+            return None;
+        }
+        let len = 1 << signal.len().ilog2();
+        let signal = &signal[signal.len() - len..];
+        let mean = signal.iter().sum::<f32>() / len as f32;
+        let mut buffer: Vec<Complex<f32>> = signal
+            .iter()
+            .enumerate()
+            .map(|(i, &x)| {
+                let window = (std::f32::consts::PI * i as f32 / len as f32).sin().powi(2);
+                Complex::new((x - mean) * window, 0.)
+            })
+            .collect();
+        FftPlanner::new().plan_fft_forward(len).process(&mut buffer);
+        let power = buffer[..=len / 2].iter().map(|x| x.norm_sqr() / len as f32).collect();
+        Some(Spectrum { power, len })
+    }
+
+    /// Frequency of bin `i`, in cycles per step.
+    pub fn frequency(&self, i: usize) -> f32 {
+        i as f32 / self.len as f32
+    }
+
+    /// Up to `count` strongest local maxima, strongest first, leaving out the constant bin.
+    pub fn peaks(&self, count: usize) -> Vec<Peak> {
+        let p = &self.power;
+        let mut peaks: Vec<Peak> = (1..p.len() - 1)
+            .filter(|&i| p[i] > p[i - 1] && p[i] >= p[i + 1])
+            .map(|i| {
+                // vertex of the parabola through the log powers around the maximum
+                let [a, b, c] = [p[i - 1], p[i], p[i + 1]].map(|x| x.max(f32::MIN_POSITIVE).ln());
+                let curvature = a - 2. * b + c;
+                let offset = if curvature < 0. { 0.5 * (a - c) / curvature } else { 0. };
+                Peak {
+                    frequency: (i as f32 + offset) / self.len as f32,
+                    power: p[i],
+                }
+            })
+            .collect();
+        peaks.sort_by(|a, b| b.power.total_cmp(&a.power));
+        peaks.truncate(count);
+        peaks
+    }
+}
+
+/// Magnitude of the 2D Fourier transform of `values`, shifted so that zero wavenumber sits at
+/// `(width / 2, height / 2)`. Index `(i, j)` holds the wavenumber `2π (i - width / 2) / width`
+/// radians per cell along x, and likewise along y. No window is applied, so the field's edges
+/// show up as a cross through the center unless they're at rest.
+pub fn spatial_magnitude(values: ArrayView2<'_, f32>) -> Array2<f32> {
+    let (width, height) = values.dim();
+    let mut transform = values.mapv(|x| Complex::new(x, 0.));
+    let mut planner = FftPlanner::new();
+    let mut buffer = vec![];
+    for axis in [Axis(0), Axis(1)] {
+        let fft = planner.plan_fft_forward(transform.len_of(axis));
+        for mut lane in transform.lanes_mut(axis) {
+            buffer.clear();
+            buffer.extend(lane.iter());
+            fft.process(&mut buffer);
+            lane.iter_mut().zip(&buffer).for_each(|(x, &y)| *x = y);
+        }
+    }
+    Array2::from_shape_fn((width, height), |(i, j)| {
+        transform[((i + width - width / 2) % width, (j + height - height / 2) % height)].norm()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn peak_of_a_pure_sine_is_at_its_frequency() {
+        // between bins, on top of an offset the mean removal takes out
+        let frequency = 0.1;
+        let omega = 2. * std::f32::consts::PI * frequency;
+        let signal: Vec<f32> = (0..1100).map(|i| 5. + (omega * i as f32).sin()).collect();
+        let spectrum = Spectrum::of(&signal).unwrap();
+        assert_eq!(spectrum.len, 1024);
+        let peaks = spectrum.peaks(3);
+        let error = (peaks[0].frequency - frequency).abs() * spectrum.len as f32;
+        assert!(error < 0.1, "peak at {}, off by {error} bins", peaks[0].frequency);
+        // the window's leakage is far weaker than the peak
+        assert!(peaks[1..].iter().all(|peak| peak.power < 1e-3 * peaks[0].power), "{peaks:?}");
+    }
+
+    #[test]
+    fn spatial_magnitude_puts_zero_wavenumber_in_the_middle() {
+        // 4 cycles across the width and 2 down the odd height
+        let (width, height) = (32, 15);
+        let values = Array2::from_shape_fn((width, height), |(x, y)| {
+            (2. * std::f32::consts::PI * (4. * x as f32 / width as f32 + 2. * y as f32 / height as f32)).cos()
+        });
+        let magnitude = spatial_magnitude(values.view());
+        let (cx, cy) = (width / 2, height / 2);
+        let peaks = [(cx + 4, cy + 2), (cx - 4, cy - 2)];
+        for (cell, &value) in magnitude.indexed_iter() {
+            if peaks.contains(&cell) {
+                assert!((value - (width * height) as f32 / 2.).abs() < 1e-2, "{cell:?}: {value}");
+            } else {
+                assert!(value < 1e-2, "{cell:?}: {value}");
+            }
+        }
+    }
+}